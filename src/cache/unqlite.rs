@@ -1,5 +1,6 @@
 extern crate unqlite;
 
+use std::collections::HashMap;
 use std::ops::Add;
 use std::time::{Duration, SystemTime};
 
@@ -14,6 +15,8 @@ const KEY: &str = "weather";
 pub struct UnQLiteCache {
     next: Box<dyn WeatherGetter>,
     ttl: Duration,
+    // время жизни отдельных видов данных
+    expirations: HashMap<WeatherQueryType, Duration>,
     unqlite: UnQLite,
     prefer_cache: bool,
 }
@@ -24,11 +27,22 @@ impl UnQLiteCache {
         UnQLiteCache {
             next,
             ttl,
+            expirations: HashMap::new(),
             unqlite,
             prefer_cache,
         }
     }
 
+    pub fn with_expirations(mut self, expirations: HashMap<WeatherQueryType, Duration>) -> Self {
+        self.expirations = expirations;
+        self
+    }
+
+    // каждый вид данных хранится под своим ключом
+    fn key(kind: WeatherQueryType) -> String {
+        format!("{}_{}", KEY, kind.name())
+    }
+
     fn is_expired(&self, key: &str) -> bool {
         let now = SystemTime::now();
         let key_ttl = format!("{}_ttl", key);
@@ -40,7 +54,7 @@ impl UnQLiteCache {
 
                 expiration < now
             }
-            Err(_) => false,
+            Err(_) => true,
         }
     }
 
@@ -54,8 +68,8 @@ impl UnQLiteCache {
         Ok(())
     }
 
-    fn get_from_cache(&self) -> Result<WeatherInfo, Error> {
-        let cached = self.unqlite.kv_fetch(KEY);
+    fn get_from_cache(&self, key: &str) -> Result<WeatherInfo, Error> {
+        let cached = self.unqlite.kv_fetch(key);
         match cached {
             Ok(data) => {
                 let s = std::str::from_utf8(&data).unwrap();
@@ -66,29 +80,192 @@ impl UnQLiteCache {
             Err(e) => Err(Error::InvalidCache(e.to_string())),
         }
     }
+
+    fn store(&self, kind: WeatherQueryType, mut weather: WeatherInfo) -> Result<(), Error> {
+        let key = Self::key(kind);
+        let now = SystemTime::now();
+        // настройка для вида данных, затем срок от провайдера (один на весь ответ), затем общая
+        let ttl = self.expirations.get(&kind).copied()
+            .or_else(|| weather.expires_at.and_then(|expires| expires.duration_since(now).ok()))
+            .unwrap_or(self.ttl);
+        // из кэша данные устаревают вместе с записью
+        weather.expires_at = Some(now + ttl);
+        let serialized = serde_json::to_string(&weather).unwrap();
+
+        self.unqlite.kv_store(&key, serialized).ok().ok_or(
+            Error::InvalidCache("store weather".to_string())
+        )?;
+        self.ttl(&key, ttl)
+    }
 }
 
 impl WeatherGetter for UnQLiteCache {
     fn get(&self, types: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
-        if !self.is_expired(&KEY) {
-            let w = self.get_from_cache();
-            if w.is_ok() {
-                return w;
+        let mut weather: Option<WeatherInfo> = None;
+        let mut missing = Vec::new();
+        for kind in types {
+            let key = Self::key(kind);
+            match self.is_expired(&key) {
+                false => match self.get_from_cache(&key) {
+                    Ok(cached) => merge(&mut weather, cached),
+                    Err(_) => missing.push(kind),
+                },
+                true => missing.push(kind),
             }
         }
-        let response = self.next.get(types);
-        if response.is_err() && self.prefer_cache {
-            return self.get_from_cache();
+        if missing.is_empty() {
+            return weather.ok_or(Error::InvalidCache("empty query".to_string()));
         }
 
-        let response = response?;
+        match self.next.get(missing.clone()) {
+            Ok(fresh) => {
+                for kind in missing {
+                    self.store(kind, fresh.select(kind))?;
+                }
+                merge(&mut weather, fresh);
+            }
+            Err(err) => {
+                if !self.prefer_cache {
+                    return Err(err);
+                }
+                // устаревшие данные лучше ошибки
+                for kind in missing {
+                    merge(&mut weather, self.get_from_cache(&Self::key(kind))?);
+                }
+            }
+        }
+        weather.ok_or(Error::InvalidCache("empty query".to_string()))
+    }
 
-        let serialized = serde_json::to_string(&response).unwrap();
+    // Свежие данные без проверки срока кэша, сохраняются в кэш. При ошибке провайдера
+    // с prefer_cache_error - данные из кэша, как в get; если в кэше их нет - ошибка провайдера.
+    fn refresh(&self, types: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        let err = match self.next.get(types.clone()) {
+            Ok(fresh) => {
+                for kind in types {
+                    self.store(kind, fresh.select(kind))?;
+                }
+                return Ok(fresh);
+            }
+            Err(err) if !self.prefer_cache => return Err(err),
+            Err(err) => err,
+        };
+        let cached: Result<Vec<WeatherInfo>, Error> = types.into_iter()
+            .map(|kind| self.get_from_cache(&Self::key(kind)))
+            .collect();
+        let mut weather = None;
+        for w in cached.map_err(|_| err)? {
+            merge(&mut weather, w);
+        }
+        weather.ok_or(Error::InvalidCache("empty query".to_string()))
+    }
+}
 
-        self.unqlite.kv_store(KEY, serialized).ok().ok_or(
-            Error::InvalidCache("store weather".to_string())
-        )?;
-        self.ttl(&KEY, self.ttl)?;
-        Ok(response)
+fn merge(weather: &mut Option<WeatherInfo>, other: WeatherInfo) {
+    match weather {
+        Some(w) => {
+            w.is_cached = w.is_cached && other.is_cached;
+            w.merge(other);
+        }
+        None => *weather = Some(other),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::temperature::Temperature;
+    use crate::temperature::Unit::Celsius;
+
+    use super::*;
+
+    // температура - номер вызова
+    struct Fake {
+        calls: Rc<Cell<u32>>,
+        fail: Rc<Cell<bool>>,
+        expires_at: Option<SystemTime>,
+    }
+
+    impl WeatherGetter for Fake {
+        fn get(&self, _: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+            self.calls.set(self.calls.get() + 1);
+            if self.fail.get() {
+                return Err(Error::InvalidRequest { text: "fake".to_string(), code: 500 });
+            }
+            Ok(WeatherInfo {
+                temp: Some(Temperature::new(self.calls.get() as f64, Celsius)),
+                expires_at: self.expires_at,
+                ..Default::default()
+            })
+        }
+    }
+
+    // ":mem:" - база unqlite в памяти
+    fn fake_cache(prefer_cache: bool) -> (UnQLiteCache, Rc<Cell<u32>>, Rc<Cell<bool>>) {
+        let calls = Rc::new(Cell::new(0));
+        let fail = Rc::new(Cell::new(false));
+        let next = Box::new(Fake { calls: calls.clone(), fail: fail.clone(), expires_at: None });
+        let cache = UnQLiteCache::new(next, ":mem:", Duration::from_secs(600), prefer_cache);
+        (cache, calls, fail)
+    }
+
+    fn temp(w: &WeatherInfo) -> Option<f64> {
+        w.temp.map(|t| t.val())
+    }
+
+    #[test]
+    fn expirations() {
+        let calls = Rc::new(Cell::new(0));
+        let hour = Duration::from_secs(3600);
+        let next = Box::new(Fake { calls: calls.clone(), fail: Rc::default(), expires_at: Some(SystemTime::now() + hour) });
+        // Current - по настройке, хотя провайдер обещает час; Daily - по провайдеру, а не общий 0
+        let cache = UnQLiteCache::new(next, ":mem:", Duration::ZERO, false)
+            .with_expirations(HashMap::from([(WeatherQueryType::Current, Duration::ZERO)]));
+        cache.get(vec![WeatherQueryType::Current, WeatherQueryType::Daily]).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        let w = cache.get(vec![WeatherQueryType::Daily]).unwrap();
+        assert_eq!((w.is_cached, calls.get()), (true, 1));
+        let w = cache.get(vec![WeatherQueryType::Current]).unwrap();
+        assert_eq!((w.is_cached, calls.get()), (false, 2));
+        // срок записи виден в данных из кэша
+        let w = cache.get(vec![WeatherQueryType::Daily]).unwrap();
+        assert!(w.expires_at.unwrap() > SystemTime::now() + hour - Duration::from_secs(60));
+    }
+
+    #[test]
+    fn refresh_bypasses_cache() {
+        let (cache, calls, _) = fake_cache(false);
+        let query = vec![WeatherQueryType::Current];
+        assert_eq!(temp(&cache.get(query.clone()).unwrap()), Some(1.0));
+        assert!(cache.get(query.clone()).unwrap().is_cached);
+        assert_eq!(calls.get(), 1);
+
+        let w = cache.refresh(query.clone()).unwrap();
+        assert_eq!((temp(&w), w.is_cached, calls.get()), (Some(2.0), false, 2));
+        // обновлённые данные в кэше
+        let w = cache.get(query).unwrap();
+        assert_eq!((temp(&w), w.is_cached, calls.get()), (Some(2.0), true, 2));
+    }
+
+    #[test]
+    fn refresh_error() {
+        let query = vec![WeatherQueryType::Current];
+        let (cache, _, fail) = fake_cache(false);
+        cache.get(query.clone()).unwrap();
+        fail.set(true);
+        assert!(matches!(cache.refresh(query.clone()), Err(Error::InvalidRequest { code: 500, .. })));
+
+        let (cache, _, fail) = fake_cache(true);
+        fail.set(true);
+        // кэш пуст - ошибка провайдера
+        assert!(matches!(cache.refresh(query.clone()), Err(Error::InvalidRequest { code: 500, .. })));
+        fail.set(false);
+        assert_eq!(temp(&cache.get(query.clone()).unwrap()), Some(2.0));
+        fail.set(true);
+        let w = cache.refresh(query).unwrap();
+        assert_eq!((temp(&w), w.is_cached), (Some(2.0), true));
+    }
+}
@@ -1,4 +1,6 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::output::output::Format;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -14,6 +16,35 @@ pub struct Args {
 
     #[clap(short, long)]
     pub debug: bool,
+
+    // именованный шаблон из [templates] или templates_dir
+    #[clap(short, long)]
+    pub template: Option<String>,
+
+    // не завершаться, выводить новую строку при изменении
+    #[clap(short, long)]
+    pub watch: bool,
+
+    // погода от forecast-get daemon
+    #[clap(short, long)]
+    pub socket: bool,
+
+    // формат вывода, по умолчанию из config.toml
+    #[clap(short, long, arg_enum)]
+    pub format: Option<Format>,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Validate the config and templates without requesting the weather
+    Check,
+    /// Switch to the next template of `views`
+    NextView,
+    /// Request the weather and serve it to `--socket` clients
+    Daemon,
 }
 
 pub fn parse() -> Args {
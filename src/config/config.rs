@@ -1,36 +1,165 @@
 extern crate directories;
 
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::path::{Path, PathBuf};
 
-use directories::ProjectDirs;
+use directories::{BaseDirs, ProjectDirs};
 use duration_string::DurationString;
 use serde::Deserialize;
 
-use crate::config::args::Args;
+use crate::config::args::{Args, Command};
 use crate::error::error::Error;
+use crate::locale::Locale;
+use crate::output::bar::Bar;
+use crate::output::output::Format;
+use crate::output::polybar::Polybar;
+use crate::output::watch::Watch;
+use crate::output::waybar::Waybar;
+use crate::units::Units;
+use crate::weather::icons::Icons;
+use crate::weather::provider::WeatherQueryType;
+use crate::weather::registry;
 
 use super::args;
+use super::metno::ConfigMetNo;
+use super::openmeteo::ConfigOpenMeteo;
+use super::openweathermap::ConfigOpenWeatherMap;
 use super::yandex::ConfigYandex;
 
 static DEFAULT_DISPLAY: &str = "{{ temperature_celsius_full }}";
+static DEFAULT_TEMPLATES_DIR: &str = "templates";
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub enum Provider {
     Yandex,
+    OpenWeatherMap,
+    OpenMeteo,
+    MetNo,
+}
+
+impl Provider {
+    // имя секции настроек провайдера в config.toml
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::Yandex => "yandex",
+            Provider::OpenWeatherMap => "openweathermap",
+            Provider::OpenMeteo => "openmeteo",
+            Provider::MetNo => "metno",
+        }
+    }
+}
+
+// как опрашивать несколько провайдеров из providers
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub enum Strategy {
+    // до первого успешного ответа
+    #[default]
+    Fallback,
+    // все сразу, с объединением ответов
+    Merge,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
-    pub provider: Provider,
+    pub provider: Option<Provider>,
+
+    #[serde(default)]
+    pub providers: Vec<Provider>,
+
+    #[serde(default)]
+    pub strategy: Strategy,
+
+    // какие данные запрашивать у провайдера
+    #[serde(default = "default_query")]
+    pub query: Vec<WeatherQueryType>,
 
     #[serde(default = "default_display")]
     pub display: String,
 
+    // шаблон из файла вместо display
+    pub display_file: Option<String>,
+
+    // именованные шаблоны, выбираются --template
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+
+    // каталог шаблонов *.hbs, по умолчанию templates рядом с config.toml
+    pub templates_dir: Option<String>,
+
+    // имя шаблона из --template
+    #[serde(skip)]
+    pub template: Option<String>,
+
+    // template - вывод по шаблону, json - данные для jq и скриптов
+    #[serde(default)]
+    pub format: Format,
+
+    // подсказка, class и percentage для --format waybar
+    #[serde(default)]
+    pub waybar: Waybar,
+
+    // постоянно работающий режим, как --watch
+    #[serde(default)]
+    pub tail: bool,
+
+    // период обновления и паузы после ошибок в режиме tail
+    #[serde(default)]
+    pub watch: Watch,
+
+    // сокет forecast-get daemon, по умолчанию $XDG_RUNTIME_DIR/forecast/forecast.sock
+    pub socket: Option<String>,
+
+    // --socket
+    #[serde(skip)]
+    pub socket_client: bool,
+
+    // шаблоны, переключаемые forecast-get next-view: ["display", "forecast"]
+    #[serde(default)]
+    pub views: Vec<String>,
+
+    // переключение views по клику для --format polybar
+    #[serde(default)]
+    pub polybar: Polybar,
+
+    // цвет и срочность для i3blocks, i3bar-json, xmobar и lemonbar
+    #[serde(default)]
+    pub bar: Bar,
+
+    // проверять шаблоны в строгом режиме перед запросом погоды
+    #[serde(default)]
+    pub strict_templates: bool,
+
+    #[serde(skip)]
+    pub command: Option<Command>,
+
+    // -c, передаётся командам, запускаемым по клику
+    #[serde(skip)]
+    pub config_file: Option<String>,
+
+    // единицы для {{ temperature }}, {{ wind_speed }} и т.п.
+    #[serde(default)]
+    pub units: Units,
+
+    // язык названий погоды, частей суток и дат: en, ru, de, uk
+    #[serde(default)]
+    pub locale: Locale,
+
+    // набор иконок и свои иконки состояний погоды
+    #[serde(default)]
+    pub icons: Icons,
+
     pub cache: Option<Cache>,
 
     //TODO: сделать динамически подключаемым либо парсить отдельно для провайдера
     pub yandex: Option<ConfigYandex>,
 
+    pub openweathermap: Option<ConfigOpenWeatherMap>,
+
+    pub openmeteo: Option<ConfigOpenMeteo>,
+
+    pub metno: Option<ConfigMetNo>,
+
     #[serde(default)]
     pub prefer_cache_error: bool,
 
@@ -38,14 +167,30 @@ pub struct Config {
     pub debug: bool,
 }
 
+// ~/ - домашний каталог
+fn resolve_path(dir: &Path, path: &str) -> String {
+    let path = match (path.strip_prefix("~/"), BaseDirs::new()) {
+        (Some(path), Some(dirs)) => dirs.home_dir().join(path),
+        _ => PathBuf::from(path),
+    };
+    dir.join(path).to_string_lossy().to_string()
+}
+
 fn default_display() -> String {
     DEFAULT_DISPLAY.to_string()
 }
 
+fn default_query() -> Vec<WeatherQueryType> {
+    WeatherQueryType::ALL.to_vec()
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Cache {
     pub enabled: bool,
     pub expiration: Option<DurationString>,
+    // время жизни отдельных видов данных, вместо expiration
+    #[serde(default)]
+    pub expirations: HashMap<WeatherQueryType, DurationString>,
 }
 
 
@@ -74,6 +219,7 @@ impl Config {
         let content = fs::read_to_string(&path).ok().ok_or(Error::FailedReadConfig)?;
         let mut cfg: Config = toml::from_str(&content)?;
 
+        cfg.resolve_paths(Path::new(&path).parent().unwrap_or_else(|| Path::new(".")));
         cfg.merge_args(&arguments);
         cfg.check()?;
         Ok(cfg)
@@ -85,9 +231,43 @@ impl Config {
         }
         self.prefer_cache_error = args.prefer_cache_error;
         self.debug = args.debug;
+        self.template = args.template.clone();
+        self.command = args.command;
+        self.config_file = args.config_file.clone();
+        self.tail |= args.watch;
+        self.socket_client = args.socket;
+        if let Some(format) = args.format {
+            self.format = format;
+        }
+    }
+
+    // относительные пути - от каталога config.toml
+    fn resolve_paths(&mut self, dir: &Path) {
+        self.display_file = self.display_file.as_deref().map(|path| resolve_path(dir, path));
+        self.socket = self.socket.as_deref().map(|path| resolve_path(dir, path));
+        let templates_dir = self.templates_dir.as_deref().unwrap_or(DEFAULT_TEMPLATES_DIR);
+        self.templates_dir = Some(resolve_path(dir, templates_dir));
+    }
+
+    // список провайдеров: providers, либо единственный provider
+    pub fn providers(&self) -> Vec<Provider> {
+        if !self.providers.is_empty() {
+            return self.providers.clone();
+        }
+        self.provider.iter().cloned().collect()
     }
 
     fn check(&self) -> Result<(), Error> {
+        let providers = self.providers();
+        if providers.is_empty() {
+            return Err(Error::InvalidConfigCheck("expected provider or providers".to_string()));
+        }
+        if self.query.is_empty() {
+            return Err(Error::InvalidConfigCheck("expected query".to_string()));
+        }
+        for provider in providers.iter() {
+            registry::check(provider, self)?;
+        }
         if let Some(cache) = &self.cache {
             if cache.enabled {
                 if cache.expiration.is_none() {
@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ConfigMetNo {
+    // широта
+    pub lat: String,
+    // долгота
+    pub lon: String,
+    // высота над уровнем моря, м
+    pub altitude: Option<i32>,
+    // user-agent, обязателен по условиям api.met.no
+    pub user_agent: String,
+    // смещение от UTC в часах для частей суток прогноза, по умолчанию по долготе
+    pub utc_offset: Option<f64>,
+}
@@ -1,4 +1,7 @@
 pub mod config;
 
-mod args;
-pub mod yandex;
\ No newline at end of file
+pub mod args;
+pub mod yandex;
+pub mod openweathermap;
+pub mod openmeteo;
+pub mod metno;
\ No newline at end of file
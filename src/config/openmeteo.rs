@@ -0,0 +1,11 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ConfigOpenMeteo {
+    // широта
+    pub lat: String,
+    // долгота
+    pub lon: String,
+    // количество дней прогноза
+    pub days: Option<u8>,
+}
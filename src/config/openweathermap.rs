@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ConfigOpenWeatherMap {
+    pub api_key: String,
+    // широта
+    pub lat: String,
+    // долгота
+    pub lon: String,
+    // язык ответа
+    pub lang: Option<String>,
+}
@@ -1,5 +1,15 @@
 use serde::Deserialize;
 
+// тариф API
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub enum Endpoint {
+    // /v2/informers: факт и две части суток
+    #[default]
+    Informers,
+    // /v2/forecast: прогноз по дням и часам
+    Forecast,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct ConfigYandex {
     pub api_key: String,
@@ -11,4 +21,13 @@ pub struct ConfigYandex {
     pub lang: Option<String>,
     // user-agent
     pub user_agent: String,
+
+    #[serde(default)]
+    pub endpoint: Endpoint,
+    // количество дней прогноза (forecast)
+    pub limit: Option<u8>,
+    // почасовой прогноз (forecast)
+    pub hours: Option<bool>,
+    // подробный прогноз осадков (forecast)
+    pub extra: Option<bool>,
 }
\ No newline at end of file
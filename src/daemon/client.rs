@@ -0,0 +1,70 @@
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use crate::daemon::daemon::{ERROR_PREFIX, Request};
+use crate::Error;
+use crate::weather::provider::{WeatherGetter, WeatherQueryType};
+use crate::weather::weather::WeatherInfo;
+
+// forecast-get --socket: погода от forecast-get daemon вместо провайдера
+pub struct SocketClient {
+    path: PathBuf,
+}
+
+impl SocketClient {
+    pub fn new(path: PathBuf) -> Self {
+        SocketClient { path }
+    }
+}
+
+impl WeatherGetter for SocketClient {
+    // query задаёт демон
+    fn get(&self, _: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        let response = request(&self.path, &Request::Weather)?;
+        Ok(serde_json::from_str(&response)?)
+    }
+}
+
+pub fn request(path: &Path, request: &Request) -> Result<String, Error> {
+    let socket_error = |err: std::io::Error| Error::Socket(format!("{}: {}", path.display(), err));
+    let mut stream = UnixStream::connect(path).map_err(socket_error)?;
+    writeln!(stream, "{}", request.line()).map_err(socket_error)?;
+    stream.shutdown(Shutdown::Write).map_err(socket_error)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(socket_error)?;
+    match response.strip_prefix(ERROR_PREFIX) {
+        Some(err) => Err(Error::Socket(err.to_string())),
+        None => Ok(response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let path = std::env::temp_dir().join(format!("forecast-client-{}.sock", std::process::id()));
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || {
+            for answer in ["4°C", "error: no weather yet"] {
+                let mut stream = listener.incoming().next().unwrap().unwrap();
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line).unwrap();
+                assert_eq!(line, "template tmux\n");
+                stream.write_all(answer.as_bytes()).unwrap();
+            }
+        });
+        let tmux = Request::Template(Some("tmux".to_string()));
+        assert_eq!(request(&path, &tmux).unwrap(), "4°C");
+        assert_eq!(request(&path, &tmux).unwrap_err().to_string(), "Socket error: no weather yet");
+        server.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
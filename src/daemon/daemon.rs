@@ -0,0 +1,209 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, Scope};
+use std::time::Duration;
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+
+use directories::ProjectDirs;
+
+use crate::config::config::Config;
+use crate::Error;
+use crate::output::json;
+use crate::output::renderer::template;
+use crate::output::views::Views;
+use crate::output::watch::{Update, Watcher};
+use crate::template::templates::Templates;
+use crate::weather::weather::WeatherInfo;
+
+const SOCKET_FILE: &str = "forecast.sock";
+
+// ответ с ошибкой: "error: текст"
+pub const ERROR_PREFIX: &str = "error: ";
+
+// сколько клиент ждёт первых данных после запуска демона
+const FIRST_DATA_TIMEOUT: Duration = Duration::from_secs(60);
+
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// socket из config.toml, иначе $XDG_RUNTIME_DIR/forecast/forecast.sock
+pub fn socket_path(c: &Config) -> PathBuf {
+    if let Some(socket) = &c.socket {
+        return PathBuf::from(socket);
+    }
+    match ProjectDirs::from("", "", "forecast").as_ref().and_then(ProjectDirs::runtime_dir) {
+        Some(dir) => dir.join(SOCKET_FILE),
+        None => std::env::temp_dir().join(format!("forecast-{}.sock", std::env::var("USER").unwrap_or_default())),
+    }
+}
+
+// Запрос клиента - одна строка:
+// weather - WeatherInfo в JSON (для forecast-get --socket), json - документ --format json,
+// template [имя] - вывод по шаблону, без имени - текущий из views или display
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    Weather,
+    Json,
+    Template(Option<String>),
+}
+
+impl FromStr for Request {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("weather"), None, _) => Ok(Request::Weather),
+            (Some("json"), None, _) => Ok(Request::Json),
+            (Some("template"), name, None) => Ok(Request::Template(name.map(str::to_string))),
+            _ => Err(format!("unknown request {}", s.trim())),
+        }
+    }
+}
+
+impl Request {
+    pub fn line(&self) -> String {
+        match self {
+            Request::Weather => "weather".to_string(),
+            Request::Json => "json".to_string(),
+            Request::Template(None) => "template".to_string(),
+            Request::Template(Some(name)) => format!("template {}", name),
+        }
+    }
+}
+
+// последние данные, clients ждут первых
+#[derive(Default)]
+struct State {
+    weather: Mutex<Option<WeatherInfo>>,
+    updated: Condvar,
+}
+
+// forecast-get daemon: один провайдер и кэш для всех клиентов сокета
+// get(true) - запрос в обход кэша по SIGUSR1, SIGUSR2 переключает views.
+// Работает до завершения процесса, возвращает только ошибку открытия сокета.
+pub fn run<G>(c: &Config, templates: &Templates, views: &Views, get: G, watcher: &Watcher) -> Error
+    where G: Fn(bool) -> Result<WeatherInfo, Error> {
+    let path = socket_path(c);
+    let listener = match bind(&path) {
+        Ok(listener) => listener,
+        Err(err) => return err,
+    };
+    let state = State::default();
+    thread::scope(|s| -> ! {
+        s.spawn(|| serve(s, &listener, &state, c, templates, views));
+        watcher.run(get, |update| {
+            match update {
+                Update::Weather(w) => {
+                    *state.weather.lock().unwrap() = Some(*w);
+                    state.updated.notify_all();
+                }
+                Update::NextView => {
+                    views.next()?;
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
+// сокет от завершившегося демона удаляется, от работающего - ошибка.
+// Другой файл по этому пути не трогаем: socket в config.toml мог указать на него по ошибке.
+fn bind(path: &Path) -> Result<UnixListener, Error> {
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(Error::Socket(format!("{}: exists and is not a socket", path.display())));
+        }
+        if UnixStream::connect(path).is_ok() {
+            return Err(Error::Socket(format!("{}: daemon is already running", path.display())));
+        }
+        let _ = fs::remove_file(path);
+    }
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    UnixListener::bind(path).map_err(|err| Error::Socket(format!("{}: {}", path.display(), err)))
+}
+
+// каждый клиент в своём потоке: ожидание первых данных не задерживает остальных
+fn serve<'scope>(s: &'scope Scope<'scope, '_>, listener: &UnixListener, state: &'scope State,
+                 c: &'scope Config, templates: &'scope Templates, views: &'scope Views) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                s.spawn(move || {
+                    if let Err(err) = respond(stream, state, c, templates, views) {
+                        eprintln!("{}", err);
+                    }
+                });
+            }
+            Err(err) => eprintln!("{}", Error::Socket(err.to_string())),
+        }
+    }
+}
+
+fn respond(mut stream: UnixStream, state: &State, c: &Config, templates: &Templates, views: &Views) -> Result<(), Error> {
+    let socket_error = |err: std::io::Error| Error::Socket(err.to_string());
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).map_err(socket_error)?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).map_err(socket_error)?;
+    let response = match answer(&line, state, c, templates, views) {
+        Ok(response) => response,
+        Err(err) => format!("{}{}", ERROR_PREFIX, err),
+    };
+    stream.write_all(response.as_bytes()).map_err(socket_error)
+}
+
+fn answer(line: &str, state: &State, c: &Config, templates: &Templates, views: &Views) -> Result<String, Error> {
+    let request: Request = line.parse().map_err(Error::Socket)?;
+    let weather = state.weather.lock().unwrap();
+    let (weather, _) = state.updated
+        .wait_timeout_while(weather, FIRST_DATA_TIMEOUT, |w| w.is_none())
+        .unwrap();
+    let w = weather.as_ref().ok_or_else(|| Error::Socket("no weather yet".to_string()))?;
+    match request {
+        Request::Weather => Ok(serde_json::to_string(w)?),
+        Request::Json => Ok(json::render(w, c.units, &c.icons, c.locale)),
+        Request::Template(name) => {
+            let name = name.as_deref().or_else(|| views.current());
+            template(c, templates, templates.get(name)?).render(w, false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request() {
+        assert_eq!("weather\n".parse::<Request>(), Ok(Request::Weather));
+        assert_eq!("template tmux".parse::<Request>(), Ok(Request::Template(Some("tmux".to_string()))));
+        assert_eq!("template".parse::<Request>(), Ok(Request::Template(None)));
+        assert!("template a b".parse::<Request>().is_err());
+        assert!("weather now".parse::<Request>().is_err());
+        for request in [Request::Json, Request::Template(Some("tmux".to_string()))] {
+            assert_eq!(request.line().parse::<Request>(), Ok(request));
+        }
+    }
+
+    #[test]
+    fn bind_existing() {
+        let path = std::env::temp_dir().join(format!("forecast-bind-{}", std::process::id()));
+        fs::write(&path, "lat = 1").unwrap();
+        assert_eq!(bind(&path).unwrap_err().to_string(),
+                   format!("Socket error: {}: exists and is not a socket", path.display()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "lat = 1");
+        fs::remove_file(&path).unwrap();
+
+        // сокет завершившегося демона
+        drop(UnixListener::bind(&path).unwrap());
+        let listener = bind(&path).unwrap();
+        assert!(bind(&path).unwrap_err().to_string().ends_with("daemon is already running"));
+        drop(listener);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -26,6 +26,21 @@ pub enum Error {
     #[error("Invalid response")]
     InvalidResponse,
 
+    #[error("Invalid template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Failed read template file: {0}")]
+    FailedReadTemplate(String),
+
+    #[error("Unknown template: {0}")]
+    UnknownTemplate(String),
+
+    #[error("Failed to save view: {0}")]
+    FailedSaveView(String),
+
+    #[error("Socket error: {0}")]
+    Socket(String),
+
     #[error("Failed to render: {}", _0)]
     FailedRender(#[from] handlebars::RenderError),
 
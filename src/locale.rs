@@ -0,0 +1,231 @@
+use std::str::FromStr;
+
+use chrono::Datelike;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::weather::weather::Condition;
+
+// Язык названий состояний погоды, частей суток и дат: locale = "ru" или "ru_RU.UTF-8"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Ru,
+    De,
+    Uk,
+}
+
+// Каталог перевода. Ключ texts - английский текст, его же выводим, если перевода нет.
+struct Catalogue {
+    texts: &'static [(&'static str, &'static str)],
+    weekdays: [&'static str; 7],
+    weekdays_short: [&'static str; 7],
+    // для ru и uk - в родительном падеже: "28 марта"
+    months: [&'static str; 12],
+    months_short: [&'static str; 12],
+}
+
+impl Locale {
+    pub fn text<'a>(&self, key: &'a str) -> &'a str {
+        self.catalogue()
+            .and_then(|c| c.texts.iter().find(|(en, _)| *en == key))
+            .map(|(_, text)| *text)
+            .unwrap_or(key)
+    }
+
+    pub fn condition(&self, condition: Condition) -> String {
+        self.text(&condition.name()).to_string()
+    }
+
+    // Формат chrono, в котором %A, %a, %B и %b заменены названиями дня недели и месяца date
+    pub fn date_format(&self, fmt: &str, date: &impl Datelike) -> String {
+        let c = match self.catalogue() {
+            Some(c) => c,
+            None => return fmt.to_string(),
+        };
+        let weekday = date.weekday().num_days_from_monday() as usize;
+        let month = date.month0() as usize;
+        let mut result = String::with_capacity(fmt.len());
+        let mut chars = fmt.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                result.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('A') => result.push_str(c.weekdays[weekday]),
+                Some('a') => result.push_str(c.weekdays_short[weekday]),
+                Some('B') => result.push_str(c.months[month]),
+                Some('b') => result.push_str(c.months_short[month]),
+                Some(next) => {
+                    result.push('%');
+                    result.push(next);
+                }
+                None => result.push('%'),
+            }
+        }
+        result
+    }
+
+    fn catalogue(&self) -> Option<&'static Catalogue> {
+        match self {
+            Locale::En => None,
+            Locale::Ru => Some(&RU),
+            Locale::De => Some(&DE),
+            Locale::Uk => Some(&UK),
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    // язык до "_", "-" или ".": ru_RU.UTF-8 -> ru
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lang = s.split(['_', '-', '.']).next().unwrap_or_default();
+        match lang.to_lowercase().as_str() {
+            "en" | "c" => Ok(Locale::En),
+            "ru" => Ok(Locale::Ru),
+            "de" => Ok(Locale::De),
+            "uk" => Ok(Locale::Uk),
+            _ => Err(format!("unknown locale {}", s)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Locale {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+static RU: Catalogue = Catalogue {
+    texts: &[
+        ("clear", "ясно"),
+        ("partly cloudy", "малооблачно"),
+        ("cloudy", "облачно с прояснениями"),
+        ("overcast", "пасмурно"),
+        ("drizzle", "морось"),
+        ("light rain", "небольшой дождь"),
+        ("rain", "дождь"),
+        ("moderate rain", "умеренно сильный дождь"),
+        ("heavy rain", "сильный дождь"),
+        ("continuous heavy rain", "длительный сильный дождь"),
+        ("showers", "ливень"),
+        ("wet snow", "дождь со снегом"),
+        ("light snow", "небольшой снег"),
+        ("snow", "снег"),
+        ("snow showers", "снегопад"),
+        ("hail", "град"),
+        ("thunderstorm", "гроза"),
+        ("thunderstorm with rain", "дождь с грозой"),
+        ("thunderstorm with hail", "гроза с градом"),
+        ("night", "ночь"),
+        ("morning", "утро"),
+        ("day", "день"),
+        ("evening", "вечер"),
+    ],
+    weekdays: ["понедельник", "вторник", "среда", "четверг", "пятница", "суббота", "воскресенье"],
+    weekdays_short: ["пн", "вт", "ср", "чт", "пт", "сб", "вс"],
+    months: ["января", "февраля", "марта", "апреля", "мая", "июня",
+             "июля", "августа", "сентября", "октября", "ноября", "декабря"],
+    months_short: ["янв", "фев", "мар", "апр", "мая", "июн", "июл", "авг", "сен", "окт", "ноя", "дек"],
+};
+
+static UK: Catalogue = Catalogue {
+    texts: &[
+        ("clear", "ясно"),
+        ("partly cloudy", "мінлива хмарність"),
+        ("cloudy", "хмарно з проясненнями"),
+        ("overcast", "похмуро"),
+        ("drizzle", "мряка"),
+        ("light rain", "невеликий дощ"),
+        ("rain", "дощ"),
+        ("moderate rain", "помірний дощ"),
+        ("heavy rain", "сильний дощ"),
+        ("continuous heavy rain", "тривалий сильний дощ"),
+        ("showers", "злива"),
+        ("wet snow", "дощ зі снігом"),
+        ("light snow", "невеликий сніг"),
+        ("snow", "сніг"),
+        ("snow showers", "снігопад"),
+        ("hail", "град"),
+        ("thunderstorm", "гроза"),
+        ("thunderstorm with rain", "дощ із грозою"),
+        ("thunderstorm with hail", "гроза з градом"),
+        ("night", "ніч"),
+        ("morning", "ранок"),
+        ("day", "день"),
+        ("evening", "вечір"),
+    ],
+    weekdays: ["понеділок", "вівторок", "середа", "четвер", "пʼятниця", "субота", "неділя"],
+    weekdays_short: ["пн", "вт", "ср", "чт", "пт", "сб", "нд"],
+    months: ["січня", "лютого", "березня", "квітня", "травня", "червня",
+             "липня", "серпня", "вересня", "жовтня", "листопада", "грудня"],
+    months_short: ["січ", "лют", "бер", "кві", "тра", "чер", "лип", "сер", "вер", "жов", "лис", "гру"],
+};
+
+static DE: Catalogue = Catalogue {
+    texts: &[
+        ("clear", "klar"),
+        ("partly cloudy", "leicht bewölkt"),
+        ("cloudy", "bewölkt"),
+        ("overcast", "bedeckt"),
+        ("drizzle", "Nieselregen"),
+        ("light rain", "leichter Regen"),
+        ("rain", "Regen"),
+        ("moderate rain", "mäßiger Regen"),
+        ("heavy rain", "starker Regen"),
+        ("continuous heavy rain", "anhaltender starker Regen"),
+        ("showers", "Regenschauer"),
+        ("wet snow", "Schneeregen"),
+        ("light snow", "leichter Schneefall"),
+        ("snow", "Schnee"),
+        ("snow showers", "Schneeschauer"),
+        ("hail", "Hagel"),
+        ("thunderstorm", "Gewitter"),
+        ("thunderstorm with rain", "Gewitter mit Regen"),
+        ("thunderstorm with hail", "Gewitter mit Hagel"),
+        ("night", "Nacht"),
+        ("morning", "Morgen"),
+        ("day", "Tag"),
+        ("evening", "Abend"),
+    ],
+    weekdays: ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"],
+    weekdays_short: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    months: ["Januar", "Februar", "März", "April", "Mai", "Juni",
+             "Juli", "August", "September", "Oktober", "November", "Dezember"],
+    months_short: ["Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez"],
+};
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn text() {
+        assert_eq!(Locale::Ru.condition(Condition::Overcast), "пасмурно");
+        assert_eq!(Locale::De.text("evening"), "Abend");
+        assert_eq!(Locale::Uk.text("12"), "12");
+        assert_eq!(Locale::En.condition(Condition::Overcast), "overcast");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("ru_RU.UTF-8".parse::<Locale>(), Ok(Locale::Ru));
+        assert_eq!("de-AT".parse::<Locale>(), Ok(Locale::De));
+        assert_eq!("UK".parse::<Locale>(), Ok(Locale::Uk));
+        assert!("fr".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn date_format() {
+        let date = NaiveDate::from_ymd_opt(2022, 3, 28).unwrap();
+        let format = |locale: Locale, fmt| date.format(&locale.date_format(fmt, &date)).to_string();
+        assert_eq!(format(Locale::Ru, "%a, %e %B"), "пн, 28 марта");
+        assert_eq!(format(Locale::De, "%A %d. %b %%B"), "Montag 28. Mär %B");
+        assert_eq!(format(Locale::En, "%A %e %B"), "Monday 28 March");
+    }
+}
@@ -5,43 +5,145 @@ Inspired by:
 
 use std::process;
 
+use config::args::Command;
 use config::config::Config;
 use error::error::Error;
-use template::template::Template;
+use template::sample;
+use template::templates::Templates;
+use output::bar;
+use output::renderer::{template, Renderer};
+use output::views::Views;
+use output::watch::{Update, Watcher};
 
 use crate::cache::unqlite::UnQLiteCache;
+use crate::daemon::client::SocketClient;
+use crate::daemon::daemon::socket_path;
 use crate::weather::provider::{WeatherGetter, WeatherQueryType};
-use crate::yandex::yandex::Yandex;
+use crate::weather::registry;
 
 mod config;
 mod error;
 mod temperature;
+mod units;
+mod locale;
+mod signals;
 mod weather;
 mod yandex;
+mod openweathermap;
+mod openmeteo;
+mod metno;
 mod template;
 mod cache;
+mod output;
+mod daemon;
 
 fn weather() -> Result<String, Error> {
     let c = Config::new()?;
-    let config = c.yandex.unwrap().clone();
-    let mut provider: Box<dyn WeatherGetter> = Box::new(Yandex::new(config));
+    // до запроса погоды: неизвестный --template - ошибка без обращения к провайдеру
+    let templates = Templates::from_config(&c)?;
+    let views = Views::new(&c.views, c.config_file.as_deref());
+    match c.command {
+        Some(Command::Check) => return check(&c, &templates),
+        Some(Command::NextView) => return Ok(views.next()?.unwrap_or_default().to_string()),
+        Some(Command::Daemon) | None => {}
+    }
+    let name = c.template.as_deref().or_else(|| views.current());
+    let mut renderer = Renderer::new(&c, &templates, templates.get(name)?)?;
+    if c.strict_templates {
+        renderer.check(&sample::weather(&c.providers()))?;
+    }
 
-    if let Some(cache) = c.cache {
-        if cache.enabled {
+    let cache = c.cache.as_ref().filter(|cache| cache.enabled && !c.socket_client);
+    let provider: Box<dyn WeatherGetter> = if c.socket_client {
+        Box::new(SocketClient::new(socket_path(&c)))
+    } else {
+        let mut provider = registry::build(&c)?;
+        if let Some(cache) = cache {
             let user = std::env::var("USER").unwrap_or("user".to_string());
             let path = format!("/home/{}/cache.unqlite", user);
+            let expirations = cache.expirations.iter()
+                .map(|(kind, d)| (*kind, (*d).into()))
+                .collect();
             provider = Box::new(UnQLiteCache::new(provider, &path,
                                                   cache.expiration.unwrap().into(),
-                                                  c.prefer_cache_error));
+                                                  c.prefer_cache_error)
+                .with_expirations(expirations));
         }
-    }
+        provider
+    };
 
-    let w = provider.get(vec![WeatherQueryType::All])?;
+    let watcher = Watcher::new(&c.watch, cache.and_then(|cache| cache.expiration).map(Into::into));
+    let get = |force| match force {
+        true => provider.refresh(c.query.clone()),
+        false => provider.get(c.query.clone()),
+    };
+    if c.command == Some(Command::Daemon) {
+        signals::install();
+        return Err(daemon::daemon::run(&c, &templates, &views, get, &watcher));
+    }
+    if c.tail {
+        signals::install();
+        if let Some(header) = bar::header(c.format) {
+            println!("{}", header);
+        }
+        // новая строка - только при изменении, при ошибке в панели остаётся прежняя
+        let mut weather = None;
+        let mut last = None;
+        watcher.run(get, |update| {
+            match update {
+                Update::Weather(w) => weather = Some(*w),
+                // явный --template не переключается
+                Update::NextView if c.template.is_none() => {
+                    if let Some(view) = views.next()? {
+                        renderer = Renderer::new(&c, &templates, templates.get(Some(view))?)?;
+                    }
+                }
+                Update::NextView => {}
+            }
+            let line = match &weather {
+                Some(w) => renderer.render(w)?,
+                None => return Ok(()),
+            };
+            if last.as_ref() != Some(&line) {
+                println!("{}", line);
+                last = Some(line);
+            }
+            Ok(())
+        })
+    }
 
-    let display = c.display.to_owned();
-    let tmpl = Template::new(display.as_str());
+    let w = provider.get(c.query.clone())?;
+    let line = renderer.render(&w)?;
+    Ok(match bar::header(c.format) {
+        Some(header) => format!("{}\n{}", header, line),
+        None => line,
+    })
+}
 
-    tmpl.render(&w, c.debug)
+// forecast-get check: настройки проверены в Config::new, шаблоны - на проверочных данных
+fn check(c: &Config, templates: &Templates) -> Result<String, Error> {
+    for view in c.views.iter() {
+        templates.get(Some(view))?;
+    }
+    let sample = sample::weather(&c.providers());
+    let checked = templates.checked();
+    let mut report = vec!["config: ok".to_string()];
+    let mut failed = 0;
+    for (name, source) in checked.iter() {
+        let result = template(c, templates, source).check(&sample);
+        match result {
+            Ok(()) => report.push(format!("template {}: ok", name)),
+            Err(err) => {
+                failed += 1;
+                report.push(format!("template {}: {}", name, err));
+            }
+        }
+    }
+    if failed > 0 {
+        println!("{}", report.join("\n"));
+        return Err(Error::InvalidTemplate(format!("{} of {} templates failed", failed, checked.len())));
+    }
+    Ok(report.join("\n"))
 }
 
 fn main() {
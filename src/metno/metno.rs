@@ -0,0 +1,331 @@
+use std::time::{Duration, SystemTime};
+
+use chrono::DateTime;
+use reqwest::blocking::Client;
+use reqwest::header::{EXPIRES, HeaderMap, LAST_MODIFIED, USER_AGENT};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::config::Provider;
+use crate::config::metno::ConfigMetNo;
+use crate::Error;
+use crate::Error::InvalidRequest;
+use crate::temperature::Temperature;
+use crate::temperature::Unit::Celsius;
+use crate::weather::provider::{WeatherGetter, WeatherQueryType};
+use crate::weather::hourly::{self, Hour};
+use crate::weather::astro;
+use crate::weather::weather::{Alert, Condition, Daytime, Forecast, ForecastPart, Precipitation, Pressure, WeatherInfo, Wind, WindDirection};
+
+const API_URL: &str = "https://api.met.no/weatherapi/locationforecast/2.0/compact?";
+const API_URL_ALERTS: &str = "https://api.met.no/weatherapi/metalerts/2.0/current.json?";
+
+pub struct MetNo {
+    config: ConfigMetNo,
+}
+
+impl MetNo {
+    pub fn new(config: ConfigMetNo) -> Self {
+        MetNo { config }
+    }
+}
+
+impl WeatherGetter for MetNo {
+    fn get(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        let mut weather = WeatherInfo {
+            provider: Some(Provider::MetNo.name().to_string()),
+            ..Default::default()
+        };
+        let forecast = [WeatherQueryType::Current, WeatherQueryType::Hourly, WeatherQueryType::Daily];
+        if forecast.iter().any(|kind| query.contains(kind)) {
+            let (res, expires_at) = self.request(API_URL, self.config.altitude)?;
+            weather = parse(res, &query, self.utc_offset()).ok_or(Error::InvalidResponse)?;
+            weather.expires_at = expires_at;
+        }
+        if query.contains(&WeatherQueryType::Alerts) {
+            let (res, expires_at) = self.request(API_URL_ALERTS, None)?;
+            weather.merge(WeatherInfo {
+                alerts: Some(parse_alerts(&res).ok_or(Error::InvalidResponse)?),
+                expires_at,
+                ..Default::default()
+            });
+        }
+        astro::complete(&mut weather, &self.config.lat, &self.config.lon);
+        Ok(weather)
+    }
+}
+
+impl MetNo {
+    // Часовой пояс места met.no не сообщает: utc_offset из настроек,
+    // иначе среднее солнечное время по долготе, 15° на час
+    fn utc_offset(&self) -> i64 {
+        let hours = self.config.utc_offset
+            .or_else(|| self.config.lon.parse::<f64>().ok().map(|lon| (lon / 15.0).round()))
+            .unwrap_or_default();
+        (hours * 3600.0).round() as i64
+    }
+
+    fn request(&self, api_url: &str, altitude: Option<i32>) -> Result<(Value, Option<SystemTime>), Error> {
+        let query_params = QueryParams {
+            lat: self.config.lat.as_str(),
+            lon: self.config.lon.as_str(),
+            altitude,
+        };
+
+        let params = serde_qs::to_string(&query_params).
+            expect("failed to format query params");
+
+        let url = api_url.to_owned() + &params;
+
+        let response = Client::new().get(url)
+            .header(USER_AGENT, self.config.user_agent.as_str())
+            .send()?;
+        if !response.status().is_success() {
+            return Err(
+                InvalidRequest {
+                    code: response.status().as_u16(),
+                    text: response.text()?.trim().to_string(),
+                }
+            );
+        }
+        let expires_at = parse_expires(response.headers(), SystemTime::now());
+        Ok((response.json()?, expires_at))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct QueryParams<'a> {
+    lat: &'a str,
+    lon: &'a str,
+    altitude: Option<i32>,
+}
+
+// Время устаревания берётся из Expires, а при его отсутствии оценивается
+// по Last-Modified как 10% от возраста данных (RFC 7234, 4.2.2).
+fn parse_expires(headers: &HeaderMap, now: SystemTime) -> Option<SystemTime> {
+    let header_time = |name| -> Option<SystemTime> {
+        let value = headers.get(name)?.to_str().ok()?;
+        let datetime = DateTime::parse_from_rfc2822(value).ok()?;
+        Some(datetime.into())
+    };
+
+    if let Some(expires) = header_time(EXPIRES) {
+        return Some(expires);
+    }
+    let age = now.duration_since(header_time(LAST_MODIFIED)?).ok()?;
+    Some(now + Duration::from_secs(age.as_secs() / 10))
+}
+
+fn parse(response: Value, query: &[WeatherQueryType], utc_offset: i64) -> Option<WeatherInfo> {
+    let timeseries = response["properties"]["timeseries"].as_array()?;
+    let current = timeseries.first()?;
+
+    let mut weather = WeatherInfo {
+        provider: Some(Provider::MetNo.name().to_string()),
+        ..Default::default()
+    };
+    if query.contains(&WeatherQueryType::Current) {
+        let details = &current["data"]["instant"]["details"];
+        let temperature = details["air_temperature"].as_f64()?;
+        let (condition, daytime) = parse_symbol(symbol_code(current));
+
+        weather.temp = Some(Temperature::new(temperature, Celsius));
+        weather.humidity = details["relative_humidity"].as_f64().map(|h| h.round() as u64);
+        weather.icon = symbol_code(current).map(|s| s.to_string());
+        weather.condition = condition;
+        weather.daytime = daytime;
+        weather.wind = parse_wind(details);
+        weather.pressure = details["air_pressure_at_sea_level"].as_f64().map(Pressure);
+        weather.precipitation = parse_precipitation(current);
+    }
+    weather.forecasts = parse_forecast(timeseries, local_time(current, utc_offset)?, query, utc_offset);
+    Some(weather)
+}
+
+fn parse_forecast(timeseries: &[Value], now: i64, query: &[WeatherQueryType], utc_offset: i64) -> Option<Forecast> {
+    let mut hours = Vec::new();
+    for item in timeseries {
+        let details = &item["data"]["instant"]["details"];
+        let (condition, daytime) = parse_symbol(symbol_code(item));
+        hours.push(Hour {
+            local: local_time(item, utc_offset)?,
+            part: ForecastPart {
+                name: String::new(),
+                temp: Temperature::new(details["air_temperature"].as_f64()?, Celsius),
+                feels_like: None,
+                humidity: details["relative_humidity"].as_f64().map(|h| h.round() as u64),
+                icon: symbol_code(item).map(|s| s.to_string()),
+                icon_url: None,
+                condition,
+                daytime,
+                wind: parse_wind(details),
+                pressure: details["air_pressure_at_sea_level"].as_f64().map(Pressure),
+                precipitation: parse_precipitation(item),
+            },
+        });
+    }
+    hourly::forecast(&hours, now, query)
+}
+
+fn parse_wind(details: &Value) -> Option<Wind> {
+    Some(Wind {
+        speed: details["wind_speed"].as_f64()?,
+        gust: details["wind_speed_of_gust"].as_f64(),
+        dir: details["wind_from_direction"].as_f64().map(WindDirection::from_degrees),
+    })
+}
+
+// осадки за ближайший час, вероятность есть только в complete
+fn parse_precipitation(item: &Value) -> Option<Precipitation> {
+    let details = &item["data"]["next_1_hours"]["details"];
+    let mm = details["precipitation_amount"].as_f64();
+    let prob = details["probability_of_precipitation"].as_f64().map(|p| p.round() as u64);
+    if mm.is_none() && prob.is_none() {
+        return None;
+    }
+    Some(Precipitation { mm, prob })
+}
+
+// https://api.met.no/weatherapi/metalerts/2.0/documentation
+fn parse_alerts(response: &Value) -> Option<Vec<Alert>> {
+    let time = |value: &Value| -> Option<SystemTime> {
+        Some(DateTime::parse_from_rfc3339(value.as_str()?).ok()?.into())
+    };
+
+    let mut alerts = Vec::new();
+    for feature in response["features"].as_array()? {
+        let properties = &feature["properties"];
+        let interval = &feature["when"]["interval"];
+        alerts.push(Alert {
+            event: properties["event"].as_str()?.to_string(),
+            title: properties["title"].as_str()
+                .or_else(|| properties["eventAwarenessName"].as_str())?
+                .to_string(),
+            description: properties["description"].as_str().map(|s| s.to_string()),
+            severity: properties["severity"].as_str().map(|s| s.to_string()),
+            start: time(&interval[0]),
+            end: time(&interval[1]),
+        });
+    }
+    Some(alerts)
+}
+
+// время в ответе в UTC, части суток считаются по времени места
+fn local_time(item: &Value, utc_offset: i64) -> Option<i64> {
+    let datetime = DateTime::parse_from_rfc3339(item["time"].as_str()?).ok()?;
+    Some(datetime.timestamp() + utc_offset)
+}
+
+fn symbol_code(item: &Value) -> Option<&str> {
+    let data = &item["data"];
+    data["next_1_hours"]["summary"]["symbol_code"].as_str()
+        .or_else(|| data["next_6_hours"]["summary"]["symbol_code"].as_str())
+}
+
+// https://api.met.no/weatherapi/weathericon/2.0/documentation
+fn parse_symbol(symbol: Option<&str>) -> (Option<Condition>, Option<Daytime>) {
+    let symbol = match symbol {
+        Some(symbol) => symbol,
+        None => return (None, None),
+    };
+    let (name, daytime) = match symbol.split_once('_') {
+        Some((name, "day")) | Some((name, "polartwilight")) => (name, Some(Daytime::Day)),
+        Some((name, "night")) => (name, Some(Daytime::Night)),
+        Some((name, _)) => (name, None),
+        None => (symbol, None),
+    };
+
+    let condition = match name {
+        "clearsky" => Condition::Clear,
+        "fair" => Condition::PartlyCloudy,
+        "partlycloudy" => Condition::Cloudy,
+        "cloudy" | "fog" => Condition::Overcast,
+        "lightrain" => Condition::LightRain,
+        "rain" => Condition::Rain,
+        "heavyrain" => Condition::HeavyRain,
+        "lightrainshowers" | "rainshowers" => Condition::Showers,
+        "heavyrainshowers" => Condition::ContinuousHeavyRain,
+        "lightsnow" | "lightsnowshowers" => Condition::LightSnow,
+        "snow" => Condition::Snow,
+        "heavysnow" | "snowshowers" | "heavysnowshowers" => Condition::SnowShowers,
+        name if name.contains("thunder") => Condition::ThunderstormWithRain,
+        name if name.contains("sleet") => Condition::WetSnow,
+        _ => return (None, daytime),
+    };
+    (Some(condition), daytime)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn symbols() {
+        assert!(matches!(parse_symbol(Some("partlycloudy_night")), (Some(Condition::Cloudy), Some(Daytime::Night))));
+        assert!(matches!(parse_symbol(Some("clearsky_day")), (Some(Condition::Clear), Some(Daytime::Day))));
+        assert!(matches!(parse_symbol(Some("heavysleetshowersandthunder_day")),
+            (Some(Condition::ThunderstormWithRain), Some(Daytime::Day))));
+        assert!(matches!(parse_symbol(Some("lightsleet")), (Some(Condition::WetSnow), None)));
+        assert!(matches!(parse_symbol(Some("unknown")), (None, None)));
+        assert!(matches!(parse_symbol(None), (None, None)));
+    }
+
+    #[test]
+    fn local() {
+        let item = serde_json::json!({ "time": "2022-03-28T10:00:00Z" });
+        let utc = local_time(&item, 0).unwrap();
+        assert_eq!(local_time(&item, 5 * 3600), Some(utc + 5 * 3600));
+
+        let config = |lon: &str, utc_offset| ConfigMetNo {
+            lat: "57.1522".to_string(),
+            lon: lon.to_string(),
+            altitude: None,
+            user_agent: String::new(),
+            utc_offset,
+        };
+        assert_eq!(MetNo::new(config("65.5272", None)).utc_offset(), 4 * 3600);
+        assert_eq!(MetNo::new(config("-74.0", None)).utc_offset(), -5 * 3600);
+        assert_eq!(MetNo::new(config("65.5272", Some(5.5))).utc_offset(), 5 * 3600 + 1800);
+    }
+
+    #[test]
+    fn alerts() {
+        let response = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "properties": {
+                    "event": "gale",
+                    "title": "Gale warning, yellow level",
+                    "description": "Strong wind up to 20 m/s",
+                    "severity": "Moderate"
+                },
+                "when": { "interval": ["2022-03-28T10:00:00+00:00", "2022-03-28T18:00:00+00:00"] }
+            }]
+        });
+        let alerts = parse_alerts(&response).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].event, "gale");
+        assert_eq!(alerts[0].severity.as_deref(), Some("Moderate"));
+        assert!(alerts[0].start < alerts[0].end);
+
+        assert!(parse_alerts(&serde_json::json!({ "features": [] })).unwrap().is_empty());
+    }
+
+    #[test]
+    fn expires_header() {
+        let now: SystemTime = DateTime::parse_from_rfc2822("Mon, 28 Mar 2022 10:00:00 GMT").unwrap().into();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(EXPIRES, HeaderValue::from_static("Mon, 28 Mar 2022 10:30:12 GMT"));
+        headers.insert(LAST_MODIFIED, HeaderValue::from_static("Mon, 28 Mar 2022 09:00:00 GMT"));
+        assert_eq!(parse_expires(&headers, now), Some(now + Duration::from_secs(30 * 60 + 12)));
+
+        headers.remove(EXPIRES);
+        assert_eq!(parse_expires(&headers, now), Some(now + Duration::from_secs(6 * 60)));
+
+        assert_eq!(parse_expires(&HeaderMap::new(), now), None);
+    }
+}
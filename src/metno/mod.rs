@@ -0,0 +1 @@
+pub mod metno;
\ No newline at end of file
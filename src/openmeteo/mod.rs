@@ -0,0 +1 @@
+pub mod openmeteo;
\ No newline at end of file
@@ -0,0 +1,278 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use reqwest::blocking::Client;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::config::Provider;
+use crate::config::openmeteo::ConfigOpenMeteo;
+use crate::Error;
+use crate::Error::InvalidRequest;
+use crate::temperature::Temperature;
+use crate::temperature::Unit::Celsius;
+use crate::weather::provider::{WeatherGetter, WeatherQueryType};
+use crate::weather::hourly::{self, Hour};
+use crate::weather::astro;
+use crate::weather::weather::{Condition, Daytime, Forecast, ForecastPart, Precipitation, Pressure, WeatherInfo, Wind, WindDirection};
+
+const API_URL: &str = "https://api.open-meteo.com/v1/forecast?";
+const VARIABLES: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,weather_code,is_day,\
+    wind_speed_10m,wind_direction_10m,wind_gusts_10m,pressure_msl,precipitation,precipitation_probability";
+const DAILY_VARIABLES: &str = "weather_code,temperature_2m_max,temperature_2m_min";
+const DEFAULT_DAYS: u8 = 2;
+
+pub struct OpenMeteo {
+    config: ConfigOpenMeteo,
+}
+
+impl OpenMeteo {
+    pub fn new(config: ConfigOpenMeteo) -> Self {
+        OpenMeteo { config }
+    }
+}
+
+impl WeatherGetter for OpenMeteo {
+    fn get(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        let has = |kind| query.contains(&kind);
+        let daily = has(WeatherQueryType::Daily);
+        let hourly = daily || has(WeatherQueryType::Hourly);
+        if !has(WeatherQueryType::Current) && !hourly {
+            return Ok(WeatherInfo {
+                provider: Some(Provider::OpenMeteo.name().to_string()),
+                ..Default::default()
+            });
+        }
+
+        let query_params = QueryParams {
+            latitude: self.config.lat.as_str(),
+            longitude: self.config.lon.as_str(),
+            current: has(WeatherQueryType::Current).then_some(VARIABLES),
+            hourly: hourly.then_some(VARIABLES),
+            daily: daily.then_some(DAILY_VARIABLES),
+            timezone: "auto",
+            wind_speed_unit: "ms",
+            forecast_days: self.config.days.unwrap_or(DEFAULT_DAYS),
+        };
+
+        let params = serde_qs::to_string(&query_params).
+            expect("failed to format query params");
+
+        let url = API_URL.to_owned() + &params;
+
+        let response = Client::new().get(url).send()?;
+        if !response.status().is_success() {
+            return Err(
+                InvalidRequest {
+                    code: response.status().as_u16(),
+                    text: response.text()?.trim().to_string(),
+                }
+            );
+        }
+        let res: Value = response.json()?;
+
+        let mut weather = parse(res, &query).ok_or(Error::InvalidResponse)?;
+        astro::complete(&mut weather, &self.config.lat, &self.config.lon);
+        Ok(weather)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct QueryParams<'a> {
+    latitude: &'a str,
+    longitude: &'a str,
+    current: Option<&'a str>,
+    hourly: Option<&'a str>,
+    daily: Option<&'a str>,
+    timezone: &'a str,
+    wind_speed_unit: &'a str,
+    forecast_days: u8,
+}
+
+fn parse(response: Value, query: &[WeatherQueryType]) -> Option<WeatherInfo> {
+    let mut weather = WeatherInfo {
+        provider: Some(Provider::OpenMeteo.name().to_string()),
+        ..Default::default()
+    };
+
+    let current = &response["current"];
+    let now = match current["time"].as_str() {
+        Some(time) => parse_time(time)?,
+        None => {
+            let unix = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+            unix + response["utc_offset_seconds"].as_i64().unwrap_or(0)
+        }
+    };
+
+    if query.contains(&WeatherQueryType::Current) {
+        let temperature = current["temperature_2m"].as_f64()?;
+        let temperature_like = current["apparent_temperature"].as_f64()?;
+
+        weather.temp = Some(Temperature::new(temperature, Celsius));
+        weather.feels_like = Some(Temperature::new(temperature_like, Celsius));
+        weather.humidity = current["relative_humidity_2m"].as_u64();
+        weather.condition = parse_condition(current["weather_code"].as_u64());
+        weather.daytime = parse_daytime(current["is_day"].as_u64());
+        weather.wind = parse_wind(current, None);
+        weather.pressure = current["pressure_msl"].as_f64().map(Pressure);
+        weather.precipitation = parse_precipitation(current, None);
+    }
+    weather.forecasts = parse_forecast(&response, now, query);
+    Some(weather)
+}
+
+fn parse_forecast(response: &Value, now: i64, query: &[WeatherQueryType]) -> Option<Forecast> {
+    let hourly = &response["hourly"];
+    let mut hours = Vec::new();
+    for (i, time) in hourly["time"].as_array()?.iter().enumerate() {
+        hours.push(Hour {
+            local: parse_time(time.as_str()?)?,
+            part: ForecastPart {
+                name: String::new(),
+                temp: Temperature::new(hourly["temperature_2m"][i].as_f64()?, Celsius),
+                feels_like: hourly["apparent_temperature"][i].as_f64()
+                    .map(|t| Temperature::new(t, Celsius)),
+                humidity: hourly["relative_humidity_2m"][i].as_u64(),
+                icon: None,
+                icon_url: None,
+                condition: parse_condition(hourly["weather_code"][i].as_u64()),
+                daytime: parse_daytime(hourly["is_day"][i].as_u64()),
+                wind: parse_wind(hourly, Some(i)),
+                pressure: hourly["pressure_msl"][i].as_f64().map(Pressure),
+                precipitation: parse_precipitation(hourly, Some(i)),
+            },
+        });
+    }
+    let mut forecast = hourly::forecast(&hours, now, query)?;
+
+    // сводка по дням точнее из daily, чем из почасовых значений
+    let daily = &response["daily"];
+    for (i, date) in daily["time"].as_array().into_iter().flatten().enumerate() {
+        if let Some(day) = forecast.days.iter_mut().find(|day| Some(day.date.as_str()) == date.as_str()) {
+            let temperature = |field: &str| daily[field][i].as_f64()
+                .map(|t| Temperature::new(t, Celsius));
+            day.temp_min = temperature("temperature_2m_min").or(day.temp_min);
+            day.temp_max = temperature("temperature_2m_max").or(day.temp_max);
+            day.condition = parse_condition(daily["weather_code"][i].as_u64()).or(day.condition);
+        }
+    }
+    Some(forecast)
+}
+
+// значение из current, либо i-е значение массива hourly
+fn value(v: &Value, field: &str, i: Option<usize>) -> Option<f64> {
+    match i {
+        Some(i) => v[field][i].as_f64(),
+        None => v[field].as_f64(),
+    }
+}
+
+fn parse_wind(v: &Value, i: Option<usize>) -> Option<Wind> {
+    Some(Wind {
+        speed: value(v, "wind_speed_10m", i)?,
+        gust: value(v, "wind_gusts_10m", i),
+        dir: value(v, "wind_direction_10m", i).map(WindDirection::from_degrees),
+    })
+}
+
+fn parse_precipitation(v: &Value, i: Option<usize>) -> Option<Precipitation> {
+    let mm = value(v, "precipitation", i);
+    let prob = value(v, "precipitation_probability", i).map(|p| p.round() as u64);
+    if mm.is_none() && prob.is_none() {
+        return None;
+    }
+    Some(Precipitation { mm, prob })
+}
+
+// время приходит в локальной зоне (timezone=auto) без смещения
+fn parse_time(s: &str) -> Option<i64> {
+    let datetime = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M").ok()?;
+    Some(Utc.from_utc_datetime(&datetime).timestamp())
+}
+
+// WMO weather interpretation codes
+// https://open-meteo.com/en/docs
+fn parse_condition(code: Option<u64>) -> Option<Condition> {
+    Some(match code? {
+        0 => Condition::Clear,
+        1 => Condition::PartlyCloudy,
+        2 => Condition::Cloudy,
+        // пасмурно и туман
+        3 | 45 | 48 => Condition::Overcast,
+        51..=57 => Condition::Drizzle,
+        61 => Condition::LightRain,
+        63 => Condition::Rain,
+        65 => Condition::HeavyRain,
+        66 | 67 => Condition::WetSnow,
+        71 | 77 => Condition::LightSnow,
+        73 => Condition::Snow,
+        75 | 85 | 86 => Condition::SnowShowers,
+        80 | 81 => Condition::Showers,
+        82 => Condition::ContinuousHeavyRain,
+        95 => Condition::Thunderstorm,
+        96 | 99 => Condition::ThunderstormWithHail,
+        _ => return None,
+    })
+}
+
+fn parse_daytime(is_day: Option<u64>) -> Option<Daytime> {
+    match is_day? {
+        1 => Some(Daytime::Day),
+        0 => Some(Daytime::Night),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/openmeteo_forecast.json");
+
+    #[test]
+    fn parse_fixture() {
+        let w = parse(serde_json::from_str(FIXTURE).unwrap(), &WeatherQueryType::ALL).unwrap();
+        assert_eq!(w.temp, Some(Temperature::new(6.4, Celsius)));
+        assert_eq!(w.feels_like, Some(Temperature::new(3.2, Celsius)));
+        assert_eq!(w.humidity, Some(62));
+        assert_eq!(w.daytime, Some(Daytime::Day));
+        assert!(matches!(w.condition, Some(Condition::Cloudy)));
+        assert_eq!(w.wind, Some(Wind { speed: 3.4, gust: Some(7.9), dir: Some(WindDirection::W) }));
+        assert_eq!(w.pressure, Some(Pressure(1008.5)));
+
+        let forecast = w.forecasts.unwrap();
+        assert_eq!(forecast.days.len(), 2);
+        assert_eq!(forecast.days[0].date, "2022-03-28");
+        assert_eq!(forecast.days[0].temp_max, Some(Temperature::new(7, Celsius)));
+        assert_eq!(forecast.days[0].hours.len(), 24);
+        assert!(matches!(forecast.days[0].condition, Some(Condition::Rain)));
+
+        let parts = forecast.parts;
+        assert_eq!(parts.len(), 2);
+
+        assert_eq!(parts[0].name, "evening");
+        assert_eq!(format!("{}", parts[0].temp), "3°C");
+        assert_eq!(format!("{}", parts[0].feels_like.unwrap()), "0°C");
+        assert_eq!(parts[0].humidity, Some(91));
+        assert_eq!(parts[0].daytime, Some(Daytime::Night));
+        assert!(matches!(parts[0].condition, Some(Condition::LightSnow)));
+
+        assert_eq!(parts[1].name, "night");
+        assert_eq!(format!("{}", parts[1].temp), "-2°C");
+        assert!(matches!(parts[1].condition, Some(Condition::Snow)));
+    }
+
+    #[test]
+    fn parse_current_only() {
+        let query = [WeatherQueryType::Current];
+        let w = parse(serde_json::from_str(FIXTURE).unwrap(), &query).unwrap();
+        assert!(w.temp.is_some());
+        assert!(w.forecasts.is_none());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let response = serde_json::json!({ "error": true, "reason": "bad" });
+        assert!(parse(response, &WeatherQueryType::ALL).is_none());
+    }
+}
@@ -0,0 +1 @@
+pub mod openweathermap;
\ No newline at end of file
@@ -0,0 +1,288 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::blocking::Client;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::config::Provider;
+use crate::config::openweathermap::ConfigOpenWeatherMap;
+use crate::Error;
+use crate::Error::InvalidRequest;
+use crate::temperature::Temperature;
+use crate::temperature::Unit::Celsius;
+use crate::weather::provider::{WeatherGetter, WeatherQueryType};
+use crate::weather::hourly::{self, Hour};
+use crate::weather::astro;
+use crate::weather::weather::{Astro, Condition, Daytime, Forecast, ForecastPart, Precipitation, Pressure, WeatherInfo, Wind, WindDirection};
+
+const API_URL_WEATHER: &str = "https://api.openweathermap.org/data/2.5/weather?";
+const API_URL_FORECAST: &str = "https://api.openweathermap.org/data/2.5/forecast?";
+const ICON_URL: &str = "https://openweathermap.org/img/wn/";
+
+pub struct OpenWeatherMap {
+    config: ConfigOpenWeatherMap,
+}
+
+impl OpenWeatherMap {
+    pub fn new(config: ConfigOpenWeatherMap) -> Self {
+        OpenWeatherMap { config }
+    }
+
+    fn request(&self, api_url: &str) -> Result<Value, Error> {
+        let query_params = QueryParams {
+            lat: self.config.lat.as_str(),
+            lon: self.config.lon.as_str(),
+            appid: self.config.api_key.as_str(),
+            units: "metric",
+            lang: self.config.lang.as_deref(),
+        };
+
+        let params = serde_qs::to_string(&query_params).
+            expect("failed to format query params");
+
+        let url = api_url.to_owned() + &params;
+
+        let response = Client::new().get(url).send()?;
+        if !response.status().is_success() {
+            return Err(
+                InvalidRequest {
+                    code: response.status().as_u16(),
+                    text: response.text()?.trim().to_string(),
+                }
+            );
+        }
+        Ok(response.json()?)
+    }
+}
+
+impl WeatherGetter for OpenWeatherMap {
+    fn get(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        let mut weather = WeatherInfo {
+            provider: Some(Provider::OpenWeatherMap.name().to_string()),
+            ..Default::default()
+        };
+        if query.contains(&WeatherQueryType::Current) {
+            let current = self.request(API_URL_WEATHER)?;
+            weather.merge(parse(current).ok_or(Error::InvalidResponse)?);
+        }
+        if query.contains(&WeatherQueryType::Daily) || query.contains(&WeatherQueryType::Hourly) {
+            let forecast = self.request(API_URL_FORECAST)?;
+            weather.forecasts = parse_forecast(&forecast, unix_now(), &query);
+            if weather.forecasts.is_none() {
+                return Err(Error::InvalidResponse);
+            }
+        }
+        astro::complete(&mut weather, &self.config.lat, &self.config.lon);
+        Ok(weather)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct QueryParams<'a> {
+    lat: &'a str,
+    lon: &'a str,
+    appid: &'a str,
+    units: &'a str,
+    lang: Option<&'a str>,
+}
+
+fn parse(current: Value) -> Option<WeatherInfo> {
+    let temperature = current["main"]["temp"].as_f64()?;
+    let temperature_like = current["main"]["feels_like"].as_f64()?;
+    let icon = current["weather"][0]["icon"].as_str()?;
+
+    Some(WeatherInfo {
+        is_cached: false,
+        provider: Some(Provider::OpenWeatherMap.name().to_string()),
+        created_at: SystemTime::now(),
+        temp: Some(Temperature::new(temperature, Celsius)),
+        feels_like: Some(Temperature::new(temperature_like, Celsius)),
+        humidity: current["main"]["humidity"].as_u64(),
+        icon: Some(icon.to_string()),
+        icon_url: Some(icon_url(icon)),
+        condition: parse_condition(current["weather"][0]["id"].as_u64()),
+        forecasts: None,
+        daytime: parse_daytime(icon),
+        wind: parse_wind(&current["wind"]),
+        pressure: current["main"]["pressure"].as_f64().map(Pressure),
+        precipitation: parse_precipitation(&current),
+        astro: Some(Astro {
+            sunrise: unix_time(&current["sys"]["sunrise"]),
+            sunset: unix_time(&current["sys"]["sunset"]),
+            moon_phase: None,
+        }),
+        alerts: None,
+        expires_at: None,
+        sources: vec![],
+    })
+}
+
+// Прогноз OpenWeatherMap приходит с шагом в 3 часа
+fn parse_forecast(response: &Value, now: i64, query: &[WeatherQueryType]) -> Option<Forecast> {
+    let timezone = response["city"]["timezone"].as_i64().unwrap_or(0);
+
+    let mut hours = Vec::new();
+    for item in response["list"].as_array()? {
+        let icon = item["weather"][0]["icon"].as_str()?;
+        hours.push(Hour {
+            local: item["dt"].as_i64()? + timezone,
+            part: ForecastPart {
+                name: String::new(),
+                temp: Temperature::new(item["main"]["temp"].as_f64()?, Celsius),
+                feels_like: item["main"]["feels_like"].as_f64()
+                    .map(|t| Temperature::new(t, Celsius)),
+                humidity: item["main"]["humidity"].as_u64(),
+                icon: Some(icon.to_string()),
+                icon_url: Some(icon_url(icon)),
+                condition: parse_condition(item["weather"][0]["id"].as_u64()),
+                daytime: parse_daytime(icon),
+                wind: parse_wind(&item["wind"]),
+                pressure: item["main"]["pressure"].as_f64().map(Pressure),
+                precipitation: parse_precipitation(item),
+            },
+        });
+    }
+    hourly::forecast(&hours, now + timezone, query)
+}
+
+fn parse_wind(wind: &Value) -> Option<Wind> {
+    Some(Wind {
+        speed: wind["speed"].as_f64()?,
+        gust: wind["gust"].as_f64(),
+        dir: wind["deg"].as_f64().map(WindDirection::from_degrees),
+    })
+}
+
+// дождь и снег за последний час (weather) или за 3 часа (forecast), pop - вероятность 0..1
+fn parse_precipitation(item: &Value) -> Option<Precipitation> {
+    let amount = |v: &Value| v["1h"].as_f64().or_else(|| v["3h"].as_f64());
+    let mm = match (amount(&item["rain"]), amount(&item["snow"])) {
+        (None, None) => None,
+        (rain, snow) => Some(rain.unwrap_or(0.0) + snow.unwrap_or(0.0)),
+    };
+    let prob = item["pop"].as_f64().map(|p| (p * 100.0).round() as u64);
+    if mm.is_none() && prob.is_none() {
+        return None;
+    }
+    Some(Precipitation { mm, prob })
+}
+
+fn unix_time(v: &Value) -> Option<SystemTime> {
+    Some(UNIX_EPOCH + Duration::from_secs(v.as_u64()?))
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn icon_url(icon: &str) -> String {
+    format!("{}{}@2x.png", ICON_URL, icon)
+}
+
+// https://openweathermap.org/weather-conditions
+fn parse_condition(id: Option<u64>) -> Option<Condition> {
+    Some(match id? {
+        200..=202 | 230..=232 => Condition::ThunderstormWithRain,
+        210..=221 => Condition::Thunderstorm,
+        300..=321 => Condition::Drizzle,
+        500 => Condition::LightRain,
+        501 => Condition::ModerateRain,
+        502 => Condition::HeavyRain,
+        503 | 504 => Condition::ContinuousHeavyRain,
+        511 => Condition::WetSnow,
+        520..=531 => Condition::Showers,
+        600 | 620 => Condition::LightSnow,
+        601 => Condition::Snow,
+        602 | 621 | 622 => Condition::SnowShowers,
+        611..=616 => Condition::WetSnow,
+        // туман, дымка, пыль и т.п.
+        701..=781 => Condition::Overcast,
+        800 => Condition::Clear,
+        801 => Condition::PartlyCloudy,
+        802 | 803 => Condition::Cloudy,
+        804 => Condition::Overcast,
+        _ => return None,
+    })
+}
+
+fn parse_daytime(icon: &str) -> Option<Daytime> {
+    match icon.chars().last()? {
+        'd' => Some(Daytime::Day),
+        'n' => Some(Daytime::Night),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn item(dt: i64, temp: f64, id: u64, icon: &str) -> Value {
+        json!({
+            "dt": dt,
+            "main": { "temp": temp, "feels_like": temp - 2.0, "humidity": 80 },
+            "weather": [{ "id": id, "icon": icon }]
+        })
+    }
+
+    #[test]
+    fn parse_current_and_parts() {
+        // 2022-03-28 10:00 UTC, timezone +5h => 15:00 local (day)
+        let current = json!({
+            "dt": 1648461600,
+            "timezone": 18000,
+            "main": { "temp": 3.6, "feels_like": -0.4, "humidity": 65, "pressure": 1012 },
+            "wind": { "speed": 4.1, "deg": 200, "gust": 7.5 },
+            "sys": { "sunrise": 1648431060, "sunset": 1648477440 },
+            "rain": { "1h": 0.3 },
+            "weather": [{ "id": 803, "icon": "04d" }]
+        });
+        let forecast = json!({
+            "city": { "timezone": 18000 },
+            "list": [
+                item(1648461600, 4.0, 803, "04d"),
+                item(1648472400, 2.0, 500, "10n"),
+                item(1648483200, 1.0, 500, "10n"),
+                item(1648494000, 0.0, 600, "13n"),
+                item(1648504800, -1.0, 600, "13n"),
+                item(1648515600, -2.0, 800, "01n"),
+            ]
+        });
+
+        let w = parse(current).unwrap();
+        assert_eq!(w.temp, Some(Temperature::new(3.6, Celsius)));
+        assert_eq!(w.humidity, Some(65));
+        assert_eq!(w.daytime, Some(Daytime::Day));
+        assert!(matches!(w.condition, Some(Condition::Cloudy)));
+        assert_eq!(w.wind, Some(Wind { speed: 4.1, gust: Some(7.5), dir: Some(WindDirection::S) }));
+        assert_eq!(w.pressure, Some(Pressure(1012.0)));
+        assert_eq!(w.precipitation, Some(Precipitation { mm: Some(0.3), prob: None }));
+        assert_eq!(w.astro.unwrap().sunrise, Some(UNIX_EPOCH + Duration::from_secs(1648431060)));
+
+        let query = [WeatherQueryType::Daily];
+        let forecast = parse_forecast(&forecast, 1648461600, &query).unwrap();
+        assert_eq!(forecast.days.len(), 2);
+        assert!(forecast.days[0].hours.is_empty());
+        assert_eq!(forecast.days[0].temp_max, Some(Temperature::new(4, Celsius)));
+
+        let parts = forecast.parts;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "evening");
+        assert_eq!(parts[0].temp, Temperature::new(1.5, Celsius));
+        assert!(matches!(parts[0].condition, Some(Condition::LightRain)));
+        assert_eq!(parts[1].name, "night");
+        assert_eq!(parts[1].temp, Temperature::new(-0.5, Celsius));
+        assert_eq!(parts[1].daytime, Some(Daytime::Night));
+    }
+
+    #[test]
+    fn condition_codes() {
+        assert!(matches!(parse_condition(Some(201)), Some(Condition::ThunderstormWithRain)));
+        assert!(matches!(parse_condition(Some(611)), Some(Condition::WetSnow)));
+        assert!(matches!(parse_condition(Some(741)), Some(Condition::Overcast)));
+        assert!(parse_condition(Some(999)).is_none());
+        assert!(parse_condition(None).is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use crate::output::output::Format;
+use crate::template::template::Template;
+use crate::weather::weather::WeatherInfo;
+
+// по умолчанию срочно, если есть предупреждения о погоде
+const DEFAULT_URGENT: &str = "{{#if alerts_count}}urgent{{/if}}";
+
+const I3BAR_HEADER: &str = "{\"version\":1}\n[";
+
+// имя блока в протоколе i3bar
+const I3BAR_NAME: &str = "weather";
+
+// [bar] в config.toml для i3blocks, i3bar-json, xmobar и lemonbar. color и urgent - шаблоны:
+// color = "{{ color current.temperature \"#5e81ac\" 0 \"#a3be8c\" 25 \"#bf616a\" }}", пустой - цвет панели,
+// urgent - срочно, если результат не пустой. urgent_color - цвет срочного для xmobar и lemonbar,
+// в протоколе i3 для этого есть "urgent".
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bar {
+    pub color: Option<String>,
+    #[serde(default = "default_urgent")]
+    pub urgent: String,
+    pub urgent_color: Option<String>,
+}
+
+impl Default for Bar {
+    fn default() -> Self {
+        Bar {
+            color: None,
+            urgent: default_urgent(),
+            urgent_color: None,
+        }
+    }
+}
+
+fn default_urgent() -> String {
+    DEFAULT_URGENT.to_string()
+}
+
+// текст по шаблону, цвет и срочность
+#[derive(Debug, PartialEq)]
+pub struct Status {
+    pub text: String,
+    pub color: Option<String>,
+    pub urgent: bool,
+}
+
+impl Status {
+    pub fn new(w: &WeatherInfo, text: &Template, color: Option<&Template>, urgent: &Template) -> Result<Self, Error> {
+        let color = match color {
+            Some(color) => Some(color.render(w, false)?.trim().to_string()).filter(|c| !c.is_empty()),
+            None => None,
+        };
+        Ok(Status {
+            text: text.render(w, false)?,
+            color,
+            urgent: !urgent.render(w, false)?.trim().is_empty(),
+        })
+    }
+}
+
+// блок i3blocks и i3bar
+#[derive(Serialize)]
+struct Block<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    full_text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+}
+
+// начало вывода: заголовок протокола i3bar
+pub fn header(format: Format) -> Option<&'static str> {
+    match format {
+        Format::I3barJson => Some(I3BAR_HEADER),
+        _ => None,
+    }
+}
+
+// строка вывода для панели format. Текст шаблона не экранируется,
+// в нём можно использовать разметку панели, например <fn=1> xmobar или %{T2} lemonbar.
+pub fn line(format: Format, status: &Status, bar: &Bar) -> String {
+    let block = Block {
+        name: None,
+        full_text: &status.text,
+        color: status.color.as_deref(),
+        urgent: status.urgent,
+    };
+    // без протокола срочности - цвет urgent_color
+    let color = match (status.urgent, &bar.urgent_color) {
+        (true, Some(urgent)) => Some(urgent.as_str()),
+        _ => status.color.as_deref(),
+    };
+    match format {
+        Format::I3blocks => serde_json::to_string(&block).expect("failed to serialize i3blocks block"),
+        Format::I3barJson => {
+            let blocks = [Block { name: Some(I3BAR_NAME), ..block }];
+            format!("{},", serde_json::to_string(&blocks).expect("failed to serialize i3bar blocks"))
+        }
+        Format::Xmobar => match color {
+            Some(color) => format!("<fc={}>{}</fc>", color, status.text),
+            None => status.text.clone(),
+        },
+        Format::Lemonbar => match color {
+            Some(color) => format!("%{{F{}}}{}%{{F-}}", color, status.text),
+            None => status.text.clone(),
+        },
+        _ => status.text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::template::template::tests::weather;
+
+    use super::*;
+
+    fn status(urgent: bool) -> Status {
+        Status { text: "☁ 4°C".to_string(), color: Some("#5e81ac".to_string()), urgent }
+    }
+
+    #[test]
+    fn status_from_templates() {
+        let color = Template::new("{{ color temperature \"#5e81ac\" 5 \"#bf616a\" }}");
+        let status = Status::new(&weather(), &Template::new("{{ temperature_full }}"), Some(&color),
+                                 &Template::new(DEFAULT_URGENT)).unwrap();
+        assert_eq!(status, Status { text: "4°C".to_string(), color: Some("#5e81ac".to_string()), urgent: false });
+    }
+
+    #[test]
+    fn lines() {
+        let bar = Bar { urgent_color: Some("#bf616a".to_string()), ..Default::default() };
+        assert_eq!(line(Format::I3blocks, &status(false), &bar), r##"{"full_text":"☁ 4°C","color":"#5e81ac"}"##);
+        assert_eq!(header(Format::I3barJson), Some("{\"version\":1}\n["));
+        assert_eq!(line(Format::I3barJson, &status(true), &bar),
+                   r##"[{"name":"weather","full_text":"☁ 4°C","color":"#5e81ac","urgent":true}],"##);
+        assert_eq!(line(Format::Xmobar, &status(false), &bar), "<fc=#5e81ac>☁ 4°C</fc>");
+        assert_eq!(line(Format::Lemonbar, &status(true), &bar), "%{F#bf616a}☁ 4°C%{F-}");
+        let plain = Status { color: None, ..status(false) };
+        assert_eq!(line(Format::Lemonbar, &plain, &Bar::default()), "☁ 4°C");
+        assert_eq!(header(Format::Xmobar), None);
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::SystemTime;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::locale::Locale;
+use crate::template::template::{nested_context, unix_time, unix_time_opt};
+use crate::units::Units;
+use crate::weather::icons::Icons;
+use crate::weather::weather::WeatherInfo;
+
+// Версия документа --format json. Меняется только при несовместимых изменениях,
+// новые поля добавляются без смены версии.
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Document {
+    version: u32,
+    cache: CacheInfo,
+    weather: Value,
+}
+
+#[derive(Serialize)]
+struct CacheInfo {
+    cached: bool,
+    #[serde(serialize_with = "unix_time")]
+    created: SystemTime,
+    // время устаревания данных по мнению провайдера
+    #[serde(serialize_with = "unix_time_opt")]
+    expires: Option<SystemTime>,
+}
+
+// weather - контекст шаблона без плоских имён (см. README "Template context"), время - unix-время
+pub fn render(w: &WeatherInfo, units: Units, icons: &Icons, locale: Locale) -> String {
+    let mut weather = nested_context(w, units, icons, locale);
+    weather["providers"] = w.sources.iter()
+        .filter_map(|source| Some((source.provider.clone()?, nested_context(source, units, icons, locale))))
+        .collect::<Map<String, Value>>()
+        .into();
+    let document = Document {
+        version: VERSION,
+        cache: CacheInfo {
+            cached: w.is_cached,
+            created: w.created_at,
+            expires: w.expires_at,
+        },
+        weather,
+    };
+    serde_json::to_string(&document).expect("failed to serialize weather")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::template::template::tests::weather;
+
+    use super::*;
+
+    #[test]
+    fn render_json() {
+        let mut w = weather();
+        w.sources = vec![WeatherInfo { provider: Some("yandex".to_string()), ..weather() }];
+        let json: Value = serde_json::from_str(&render(&w, Units::default(), &Icons::default(), Locale::Ru)).unwrap();
+
+        assert_eq!(json["version"], VERSION);
+        assert_eq!(json["cache"]["cached"], false);
+        assert!(json["cache"]["created"].is_u64());
+        assert!(json["cache"]["expires"].is_null());
+        let weather = &json["weather"];
+        assert_eq!(weather["current"]["temperature"]["celsius_raw"], 3.6);
+        assert_eq!(weather["current"]["condition_code"], "Cloudy");
+        assert_eq!(weather["forecast"]["parts"][0]["key"], "evening");
+        assert!(weather["astro"]["sunrise"].is_u64());
+        assert_eq!(weather["providers"]["yandex"]["current"]["humidity"], 65);
+        // плоские имена только для шаблонов
+        assert!(weather.get("temperature_celsius").is_none());
+        assert!(weather["providers"]["yandex"].get("temperature_celsius").is_none());
+    }
+}
@@ -0,0 +1,8 @@
+pub mod output;
+pub mod json;
+pub mod waybar;
+pub mod bar;
+pub mod polybar;
+pub mod views;
+pub mod watch;
+pub mod renderer;
@@ -0,0 +1,30 @@
+use clap::ArgEnum;
+use serde::Deserialize;
+
+// Формат вывода: шаблон handlebars или данные для других программ
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    #[default]
+    Template,
+    Json,
+    // custom-модуль waybar: {"text", "tooltip", "class", "percentage"}
+    Waybar,
+    // блок i3blocks с format=json
+    I3blocks,
+    // протокол i3bar: заголовок и массивы блоков
+    I3barJson,
+    // разметка <fc=...> xmobar
+    Xmobar,
+    // разметка %{F...} lemonbar
+    Lemonbar,
+    // шаблон с тегами polybar, % в значениях экранируется, переключение views по клику
+    Polybar,
+}
+
+impl Format {
+    // вывод по шаблону с цветом и срочностью из [bar]
+    pub fn is_bar(&self) -> bool {
+        matches!(self, Format::I3blocks | Format::I3barJson | Format::Xmobar | Format::Lemonbar)
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+// [polybar] в config.toml
+// view_button - кнопка мыши, переключающая views (1 - левая), 0 - без переключения
+#[derive(Debug, Clone, Deserialize)]
+pub struct Polybar {
+    #[serde(default = "default_view_button")]
+    pub view_button: u8,
+}
+
+impl Default for Polybar {
+    fn default() -> Self {
+        Polybar {
+            view_button: default_view_button(),
+        }
+    }
+}
+
+fn default_view_button() -> u8 {
+    1
+}
+
+// Экранирование значений {{ }} в режиме polybar: % в тексте провайдера не должен начинать тег
+pub fn escape(text: &str) -> String {
+    text.replace('%', "%%")
+}
+
+// %{A1:cmd:}, закрывается %{A}. Двоеточие в команде экранируется.
+pub fn action(button: u8, command: &str) -> String {
+    format!("%{{A{}:{}:}}", button, command.replace(':', "\\:"))
+}
+
+// Вывод для polybar. next_view - команда переключения видов, если их несколько.
+pub fn render(text: String, polybar: &Polybar, next_view: Option<&str>) -> String {
+    match next_view {
+        Some(command) if polybar.view_button > 0 => format!("{}{}%{{A}}", action(polybar.view_button, command), text),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_polybar() {
+        assert_eq!(escape("100% %{F-}"), "100%% %%{F-}");
+        let polybar = Polybar::default();
+        assert_eq!(render("4°C".to_string(), &polybar, Some("forecast-get -c /a:b next-view")),
+                   "%{A1:forecast-get -c /a\\:b next-view:}4°C%{A}");
+        assert_eq!(render("4°C".to_string(), &polybar, None), "4°C");
+        assert_eq!(render("4°C".to_string(), &Polybar { view_button: 0 }, Some("next")), "4°C");
+    }
+}
@@ -0,0 +1,109 @@
+use crate::config::config::Config;
+use crate::Error;
+use crate::output::{bar, json, polybar, waybar};
+use crate::output::bar::Status;
+use crate::output::output::Format;
+use crate::template::template::Template;
+use crate::template::templates::Templates;
+use crate::weather::weather::WeatherInfo;
+
+// Шаблоны выбранного формата вывода: основной, подсказка waybar, цвет и срочность [bar]
+pub struct Renderer<'a> {
+    c: &'a Config,
+    tmpl: Template<'a>,
+    tooltip: Option<Template<'a>>,
+    color: Option<Template<'a>>,
+    urgent: Template<'a>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(c: &'a Config, templates: &'a Templates, source: &'a str) -> Result<Self, Error> {
+        let tooltip = match (&c.format, &c.waybar.tooltip) {
+            (Format::Waybar, Some(name)) => Some(template(c, templates, templates.get(Some(name))?)),
+            _ => None,
+        };
+        Ok(Renderer {
+            c,
+            tmpl: template(c, templates, source),
+            tooltip,
+            color: c.bar.color.as_deref().map(|source| template(c, templates, source)),
+            urgent: template(c, templates, &c.bar.urgent),
+        })
+    }
+
+    // проверка используемых форматом шаблонов, см. Template::check
+    pub fn check(&self, sample: &WeatherInfo) -> Result<(), Error> {
+        match self.c.format {
+            Format::Json => Ok(()),
+            format if format.is_bar() => {
+                self.tmpl.check(sample)?;
+                self.color.iter().chain(Some(&self.urgent)).try_for_each(|t| t.check(sample))
+            }
+            _ => {
+                self.tmpl.check(sample)?;
+                self.tooltip.iter().try_for_each(|t| t.check(sample))
+            }
+        }
+    }
+
+    // строка вывода без заголовка протокола (bar::header)
+    pub fn render(&self, w: &WeatherInfo) -> Result<String, Error> {
+        let c = self.c;
+        match c.format {
+            Format::Template => self.tmpl.render(w, c.debug),
+            Format::Json => Ok(json::render(w, c.units, &c.icons, c.locale)),
+            Format::Waybar => waybar::render(w, &self.tmpl, self.tooltip.as_ref(), &c.waybar, c.units),
+            Format::Polybar => {
+                let next_view = (c.views.len() > 1).then(|| match c.tail {
+                    // tail = true: шаблон меняет этот же процесс по SIGUSR2
+                    true => format!("kill -USR2 {}", std::process::id()),
+                    false => next_view_command(c.config_file.as_deref()),
+                });
+                Ok(polybar::render(self.tmpl.render(w, c.debug)?, &c.polybar, next_view.as_deref()))
+            }
+            Format::I3blocks | Format::I3barJson | Format::Xmobar | Format::Lemonbar => {
+                let status = Status::new(w, &self.tmpl, self.color.as_ref(), &self.urgent)?;
+                Ok(bar::line(c.format, &status, &c.bar))
+            }
+        }
+    }
+}
+
+pub fn template<'a>(c: &Config, templates: &'a Templates, source: &'a str) -> Template<'a> {
+    let tmpl = Template::new(source)
+        .with_units(c.units)
+        .with_icons(c.icons.clone())
+        .with_locale(c.locale)
+        .with_partials(templates.partials());
+    match c.format {
+        Format::Polybar => tmpl.with_escape(polybar::escape),
+        _ => tmpl,
+    }
+}
+
+// forecast-get next-view с тем же config.toml, для клика в polybar (выполняется через sh -c)
+fn next_view_command(config_file: Option<&str>) -> String {
+    let exe = std::env::current_exe()
+        .map(|exe| exe.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "forecast-get".to_string());
+    match config_file {
+        Some(path) => format!("{} -c {} next-view", shell_quote(&exe), shell_quote(path)),
+        None => format!("{} next-view", shell_quote(&exe)),
+    }
+}
+
+// 'текст' для sh, кавычка внутри - '\''
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote() {
+        assert_eq!(shell_quote("/home/me/my config.toml"), "'/home/me/my config.toml'");
+        assert_eq!(shell_quote("it's;$(rm)"), "'it'\\''s;$(rm)'");
+    }
+}
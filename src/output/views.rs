@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+use crate::Error;
+
+// файл с номером текущего вида в каталоге кэша, для -c config.toml - свой
+const VIEW_FILE: &str = "view";
+
+// Виды - шаблоны из views в config.toml, между которыми переключает forecast-get next-view.
+// Номер текущего вида хранится в файле, чтобы его видели следующие запуски.
+pub struct Views<'a> {
+    names: &'a [String],
+    path: Option<PathBuf>,
+}
+
+impl<'a> Views<'a> {
+    pub fn new(names: &'a [String], config_file: Option<&str>) -> Self {
+        Views {
+            names,
+            path: ProjectDirs::from("", "", "forecast").map(|dirs| dirs.cache_dir().join(view_file(config_file))),
+        }
+    }
+
+    // без views - None, шаблон по умолчанию
+    pub fn current(&self) -> Option<&'a str> {
+        self.names.get(self.index()).map(String::as_str)
+    }
+
+    pub fn next(&self) -> Result<Option<&'a str>, Error> {
+        if self.names.is_empty() {
+            return Ok(None);
+        }
+        let index = (self.index() + 1) % self.names.len();
+        if let Some(path) = &self.path {
+            let save = || {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(path, index.to_string())
+            };
+            save().map_err(|err| Error::FailedSaveView(format!("{}: {}", path.display(), err)))?;
+        }
+        Ok(Some(&self.names[index]))
+    }
+
+    // вид мог пропасть из views - тогда по кругу
+    fn index(&self) -> usize {
+        let index: usize = self.path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|index| index.trim().parse().ok())
+            .unwrap_or_default();
+        index.checked_rem(self.names.len()).unwrap_or_default()
+    }
+}
+
+// панели с разными config.toml переключают виды независимо: view-home_me_bar_toml
+fn view_file(config_file: Option<&str>) -> String {
+    let path = match config_file {
+        Some(path) => Path::new(path).canonicalize().unwrap_or_else(|_| PathBuf::from(path)),
+        None => return VIEW_FILE.to_string(),
+    };
+    let key: String = path.to_string_lossy().trim_start_matches('/').chars()
+        .map(|ch| if ch.is_alphanumeric() || ch == '-' { ch } else { '_' })
+        .collect();
+    format!("{}-{}", VIEW_FILE, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle() {
+        let path = std::env::temp_dir().join(format!("forecast-view-{}", std::process::id()));
+        let names = vec!["display".to_string(), "forecast".to_string()];
+        let views = Views { names: &names, path: Some(path.join(VIEW_FILE)) };
+        assert_eq!(views.current(), Some("display"));
+        assert_eq!(views.next().unwrap(), Some("forecast"));
+        assert_eq!(views.current(), Some("forecast"));
+        assert_eq!(views.next().unwrap(), Some("display"));
+
+        fs::write(path.join(VIEW_FILE), "5").unwrap();
+        assert_eq!(views.current(), Some("forecast"));
+        fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(view_file(None), "view");
+        assert_eq!(view_file(Some("/nonexistent/my bar.toml")), "view-nonexistent_my_bar_toml");
+
+        let empty = Views { names: &[], path: None };
+        assert_eq!(empty.current(), None);
+        assert_eq!(empty.next().unwrap(), None);
+    }
+}
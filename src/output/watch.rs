@@ -0,0 +1,208 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use duration_string::DurationString;
+use serde::Deserialize;
+
+use crate::Error;
+use crate::signals;
+use crate::weather::weather::WeatherInfo;
+
+// период обновления без watch.interval и кэша
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// первая пауза после ошибки, дальше вдвое больше до max_backoff
+const MIN_BACKOFF: Duration = Duration::from_secs(30);
+
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+// не чаще, даже если данные уже устарели
+const MIN_DELAY: Duration = Duration::from_secs(10);
+
+// как часто во время паузы проверяются сигналы
+const SIGNAL_POLL: Duration = Duration::from_millis(250);
+
+// [watch] в config.toml для --watch или tail = true
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Watch {
+    // по умолчанию cache.expiration
+    pub interval: Option<DurationString>,
+    pub max_backoff: Option<DurationString>,
+}
+
+pub enum Update {
+    Weather(Box<WeatherInfo>),
+    // SIGUSR2: следующий шаблон из views
+    NextView,
+}
+
+// Постоянно работающий режим (--watch, daemon): запрос погоды по расписанию
+pub struct Watcher {
+    interval: Duration,
+    max_backoff: Duration,
+}
+
+impl Watcher {
+    pub fn new(watch: &Watch, cache_expiration: Option<Duration>) -> Self {
+        Watcher {
+            interval: watch.interval.map(Into::into).or(cache_expiration).unwrap_or(DEFAULT_INTERVAL),
+            max_backoff: watch.max_backoff.map(Into::into).unwrap_or(DEFAULT_MAX_BACKOFF),
+        }
+    }
+
+    // update получает каждые новые данные, ошибки get и update - пауза и повтор.
+    // get(true) - по SIGUSR1, в обход кэша. Работает до завершения процесса.
+    pub fn run<G, U>(&self, get: G, mut update: U) -> !
+        where G: Fn(bool) -> Result<WeatherInfo, Error>,
+              U: FnMut(Update) -> Result<(), Error> {
+        let mut failures = 0;
+        let mut force = false;
+        loop {
+            let delay = self.step(get(force), &mut update, &mut failures);
+            force = self.wait(delay, &mut update);
+        }
+    }
+
+    // пауза до следующего запроса после ответа get
+    fn step<U>(&self, result: Result<WeatherInfo, Error>, update: &mut U, failures: &mut u32) -> Duration
+        where U: FnMut(Update) -> Result<(), Error> {
+        let result = result.and_then(|w| {
+            let delay = self.delay(&w, SystemTime::now());
+            update(Update::Weather(Box::new(w))).map(|_| delay)
+        });
+        match result {
+            Ok(Some(delay)) => {
+                *failures = 0;
+                delay
+            }
+            // устаревшие данные показаны, но провайдер повторяется с паузой, как после ошибки
+            Ok(None) => {
+                *failures += 1;
+                self.backoff(*failures)
+            }
+            Err(err) => {
+                *failures += 1;
+                eprintln!("{}", err);
+                self.backoff(*failures)
+            }
+        }
+    }
+
+    // true, если пауза прервана SIGUSR1
+    fn wait<U>(&self, delay: Duration, update: &mut U) -> bool
+        where U: FnMut(Update) -> Result<(), Error> {
+        let deadline = Instant::now() + delay;
+        loop {
+            if signals::refresh_requested() {
+                return true;
+            }
+            if signals::next_view_requested() {
+                if let Err(err) = update(Update::NextView) {
+                    eprintln!("{}", err);
+                }
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            thread::sleep(left.min(SIGNAL_POLL));
+        }
+    }
+
+    // Следующий запрос через interval от получения данных (из кэша - от исходного запроса),
+    // но не позже устаревания данных по мнению провайдера.
+    // None - кэш отдал уже устаревшие данные вместо ошибки провайдера (prefer_cache_error).
+    fn delay(&self, w: &WeatherInfo, now: SystemTime) -> Option<Duration> {
+        let refresh = w.created_at + self.interval;
+        let next = match w.expires_at {
+            Some(expires) if expires < refresh => expires,
+            _ => refresh,
+        };
+        match next.duration_since(now) {
+            Ok(delay) => Some(delay.max(MIN_DELAY)),
+            Err(_) if w.is_cached => None,
+            Err(_) => Some(MIN_DELAY),
+        }
+    }
+
+    fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        MIN_BACKOFF.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::cache::unqlite::UnQLiteCache;
+    use crate::weather::provider::{WeatherGetter, WeatherQueryType};
+
+    use super::*;
+
+    #[test]
+    fn delay() {
+        let watcher = Watcher::new(&Watch::default(), Some(Duration::from_secs(600)));
+        let now = SystemTime::now();
+        let w = WeatherInfo { created_at: now - Duration::from_secs(100), ..Default::default() };
+        assert_eq!(watcher.delay(&w, now), Some(Duration::from_secs(500)));
+
+        let w = WeatherInfo { expires_at: Some(now + Duration::from_secs(60)), ..w };
+        assert_eq!(watcher.delay(&w, now), Some(Duration::from_secs(60)));
+
+        let w = WeatherInfo { created_at: now - Duration::from_secs(3600), expires_at: None, ..w };
+        assert_eq!(watcher.delay(&w, now), Some(MIN_DELAY));
+
+        let w = WeatherInfo { is_cached: true, ..w };
+        assert_eq!(watcher.delay(&w, now), None);
+    }
+
+    // провайдер отвечает один раз, дальше ошибка
+    struct Failing {
+        calls: Rc<Cell<u32>>,
+    }
+
+    impl WeatherGetter for Failing {
+        fn get(&self, _: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+            self.calls.set(self.calls.get() + 1);
+            if self.calls.get() > 1 {
+                return Err(Error::InvalidRequest { text: "fake".to_string(), code: 500 });
+            }
+            Ok(WeatherInfo { created_at: SystemTime::now() - Duration::from_secs(3600), ..Default::default() })
+        }
+    }
+
+    #[test]
+    fn stale_cache_backoff() {
+        let calls = Rc::new(Cell::new(0));
+        // срок кэша 0: данные устаревают сразу, prefer_cache_error отдаёт их вместо ошибки
+        let cache = UnQLiteCache::new(Box::new(Failing { calls: calls.clone() }), ":mem:", Duration::ZERO, true);
+        let watcher = Watcher::new(&Watch::default(), Some(Duration::from_secs(600)));
+        let query = vec![WeatherQueryType::Current];
+        let mut updates = 0;
+        let mut update = |_: Update| {
+            updates += 1;
+            Ok(())
+        };
+        let mut failures = 0;
+
+        assert_eq!(watcher.step(cache.get(query.clone()), &mut update, &mut failures), MIN_DELAY);
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(watcher.step(cache.get(query.clone()), &mut update, &mut failures), MIN_BACKOFF);
+        assert_eq!(watcher.step(cache.get(query), &mut update, &mut failures), MIN_BACKOFF * 2);
+        assert_eq!((calls.get(), failures), (3, 2));
+        assert_eq!(updates, 3);
+    }
+
+    #[test]
+    fn backoff() {
+        let watch = Watch { max_backoff: Some(Duration::from_secs(100).into()), ..Default::default() };
+        let watcher = Watcher::new(&watch, None);
+        assert_eq!(watcher.interval, DEFAULT_INTERVAL);
+        assert_eq!(watcher.backoff(1), Duration::from_secs(30));
+        assert_eq!(watcher.backoff(2), Duration::from_secs(60));
+        assert_eq!(watcher.backoff(3), Duration::from_secs(100));
+        assert_eq!(watcher.backoff(100), Duration::from_secs(100));
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use crate::template::template::Template;
+use crate::units::Units;
+use crate::weather::weather::{Daytime, WeatherInfo};
+
+const DEFAULT_PERCENTAGE: &str = "humidity";
+
+// [waybar] в config.toml
+// tooltip = "tooltip"    - именованный шаблон подсказки
+// percentage = "humidity" - поле контекста для percentage
+// cold = 0, hot = 25     - пороги температуры в units для class "cold" и "hot"
+#[derive(Debug, Clone, Deserialize)]
+pub struct Waybar {
+    pub tooltip: Option<String>,
+    #[serde(default = "default_percentage")]
+    pub percentage: String,
+    pub cold: Option<f64>,
+    pub hot: Option<f64>,
+}
+
+impl Default for Waybar {
+    fn default() -> Self {
+        Waybar {
+            tooltip: None,
+            percentage: default_percentage(),
+            cold: None,
+            hot: None,
+        }
+    }
+}
+
+fn default_percentage() -> String {
+    DEFAULT_PERCENTAGE.to_string()
+}
+
+// строка custom-модуля waybar с return-type = "json"
+#[derive(Serialize)]
+struct Output {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tooltip: Option<String>,
+    class: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percentage: Option<u64>,
+}
+
+pub fn render(w: &WeatherInfo, text: &Template, tooltip: Option<&Template>,
+              waybar: &Waybar, units: Units) -> Result<String, Error> {
+    let output = Output {
+        text: text.render(w, false)?,
+        tooltip: tooltip.map(|t| t.render(w, false)).transpose()?,
+        class: classes(w, waybar, units),
+        percentage: text.field(w, &waybar.percentage)
+            .and_then(|v| v.as_f64())
+            .map(|v| v.round().clamp(0.0, 100.0) as u64),
+    };
+    Ok(serde_json::to_string(&output).expect("failed to serialize waybar output"))
+}
+
+// состояние погоды в kebab-case (partly-cloudy), night ночью, cold и hot по порогам
+fn classes(w: &WeatherInfo, waybar: &Waybar, units: Units) -> Vec<String> {
+    let mut classes = vec![];
+    if let Some(Ok(serde_json::Value::String(code))) = w.condition.map(serde_json::to_value) {
+        classes.push(kebab_case(&code));
+    }
+    if w.daytime == Some(Daytime::Night) {
+        classes.push("night".to_string());
+    }
+    if let Some(temp) = w.temp.map(|t| t.as_unit(units.temperature).val()) {
+        if waybar.cold.is_some_and(|cold| temp < cold) {
+            classes.push("cold".to_string());
+        }
+        if waybar.hot.is_some_and(|hot| temp >= hot) {
+            classes.push("hot".to_string());
+        }
+    }
+    classes
+}
+
+fn kebab_case(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 4);
+    for (i, ch) in s.chars().enumerate() {
+        if ch.is_uppercase() && i > 0 {
+            result.push('-');
+        }
+        result.extend(ch.to_lowercase());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::template::template::tests::weather;
+
+    use super::*;
+
+    #[test]
+    fn render_waybar() {
+        let w = weather();
+        let text = Template::new("{{ condition_icon }} {{ temperature_full }}");
+        let tooltip = Template::new("{{ condition }}\n{{ humidity }}%");
+        let waybar = Waybar { cold: Some(5.0), hot: Some(25.0), ..Default::default() };
+        let out: Value = serde_json::from_str(&render(&w, &text, Some(&tooltip), &waybar, Units::default()).unwrap()).unwrap();
+
+        assert_eq!(out["text"], Template::new("{{ condition_icon }} 4°C").render(&w, false).unwrap());
+        assert_eq!(out["tooltip"], "cloudy\n65%");
+        assert_eq!(out["class"], serde_json::json!(["cloudy", "cold"]));
+        assert_eq!(out["percentage"], 65);
+
+        let waybar = Waybar { percentage: "missing".to_string(), ..Default::default() };
+        let out: Value = serde_json::from_str(&render(&w, &text, None, &waybar, Units::default()).unwrap()).unwrap();
+        assert!(out.get("tooltip").is_none());
+        assert!(out.get("percentage").is_none());
+    }
+
+    #[test]
+    fn kebab() {
+        assert_eq!(kebab_case("PartlyCloudy"), "partly-cloudy");
+        assert_eq!(kebab_case("Clear"), "clear");
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REFRESH: AtomicBool = AtomicBool::new(false);
+static NEXT_VIEW: AtomicBool = AtomicBool::new(false);
+
+// В режимах --watch и daemon: SIGUSR1 - запрос погоды в обход кэша, SIGUSR2 - следующий шаблон из views.
+// Обработчик только ставит флаг, флаги проверяет Watcher.
+pub fn install() {
+    let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler);
+        libc::signal(libc::SIGUSR2, handler);
+    }
+}
+
+extern "C" fn handle(signal: libc::c_int) {
+    match signal {
+        libc::SIGUSR1 => REFRESH.store(true, Ordering::SeqCst),
+        libc::SIGUSR2 => NEXT_VIEW.store(true, Ordering::SeqCst),
+        _ => {}
+    }
+}
+
+// был ли SIGUSR1 после прошлой проверки
+pub fn refresh_requested() -> bool {
+    REFRESH.swap(false, Ordering::SeqCst)
+}
+
+// был ли SIGUSR2 после прошлой проверки
+pub fn next_view_requested() -> bool {
+    NEXT_VIEW.swap(false, Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags() {
+        install();
+        unsafe {
+            libc::raise(libc::SIGUSR1);
+        }
+        assert!(refresh_requested());
+        assert!(!refresh_requested());
+        unsafe {
+            libc::raise(libc::SIGUSR2);
+        }
+        assert!(next_view_requested());
+        assert!(!next_view_requested());
+    }
+}
@@ -1,12 +1,18 @@
 use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Unit {
+    // в [units] в нижнем регистре, как остальные единицы
+    #[serde(alias = "celsius")]
     Celsius,
+    #[serde(alias = "fahrenheit")]
     Fahrenheit,
+    #[serde(alias = "kelvin")]
     Kelvin,
 }
 
@@ -21,61 +27,123 @@ impl Display for Unit {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, Serialize, Deserialize)]
-pub struct Temperature(i16, Unit);
+// Способ округления при выводе
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Rounding {
+    // до ближайшего, половина - от нуля
+    #[default]
+    Nearest,
+    Floor,
+    Ceil,
+    // отбрасывание дробной части
+    Trunc,
+}
+
+impl Rounding {
+    pub fn apply(self, val: f64, precision: u8) -> f64 {
+        let k = 10f64.powi(precision as i32);
+        let scaled = val * k;
+        let rounded = match self {
+            Rounding::Nearest => scaled.round(),
+            Rounding::Floor => scaled.floor(),
+            Rounding::Ceil => scaled.ceil(),
+            Rounding::Trunc => scaled.trunc(),
+        } / k;
+        // без "-0"
+        if rounded == 0.0 { 0.0 } else { rounded }
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nearest" | "round" => Ok(Rounding::Nearest),
+            "floor" => Ok(Rounding::Floor),
+            "ceil" => Ok(Rounding::Ceil),
+            "trunc" => Ok(Rounding::Trunc),
+            _ => Err(format!("unknown rounding {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Temperature(f64, Unit);
+
+// точность сравнения температур в разных единицах, K
+const EPSILON: f64 = 1e-6;
 
 impl Temperature {
-    pub fn new(val: i16, unit: Unit) -> Temperature {
-        Temperature(val, unit)
+    pub fn new(val: impl Into<f64>, unit: Unit) -> Temperature {
+        Temperature(val.into(), unit)
     }
     pub fn unit(&self) -> Unit {
         self.1
     }
-    pub fn val(&self) -> i16 {
+    pub fn val(&self) -> f64 {
         self.0
     }
     pub fn as_unit(self, unit: Unit) -> Temperature {
         use Unit::*;
-        match (self.val(), self.unit(), unit) {
-            // Kelvin to Celsius
-            (val, Kelvin, Celsius) => Temperature::new(val - 273.15 as i16, Celsius),
-            // Kelvin to Fahrenheit
-            (val, Kelvin, Fahrenheit) => Temperature::new(val * 9 / 5 - 459.67 as i16, Fahrenheit),
-            // Celsius to Kelvin
-            (val, Celsius, Kelvin) => Temperature::new(val + 273.15 as i16, Kelvin),
-            // Celsius to Fahrenheit
-            (val, Celsius, Fahrenheit) => Temperature::new(val * 9 / 5 + 32 as i16, Fahrenheit),
-            // Fahrenheit to Kelvin
-            (val, Fahrenheit, Kelvin) => Temperature::new((val + 459.67 as i16) * 5 / 9 as i16, Kelvin),
-            // Fahrenheit to Celsius
-            (val, Fahrenheit, Celsius) => Temperature::new((val - 32) * 5 / 9 as i16, Celsius),
-            // Identity
-            _ => self,
+        if self.unit() == unit {
+            return self;
         }
+        let kelvin = match self.unit() {
+            Kelvin => self.val(),
+            Celsius => self.val() + 273.15,
+            Fahrenheit => (self.val() + 459.67) * 5.0 / 9.0,
+        };
+        let val = match unit {
+            Kelvin => kelvin,
+            Celsius => kelvin - 273.15,
+            Fahrenheit => kelvin * 9.0 / 5.0 - 459.67,
+        };
+        Temperature(val, unit)
+    }
+    // значение, округлённое до precision знаков после запятой
+    pub fn round(self, precision: u8, rounding: Rounding) -> Temperature {
+        Temperature(rounding.apply(self.val(), precision), self.unit())
+    }
+    // сравнение и хеширование идут по ключу в кельвинах с точностью EPSILON,
+    // поэтому Eq и Ord согласованы между единицами
+    fn key(&self) -> i64 {
+        (self.as_unit(Unit::Kelvin).val() / EPSILON).round() as i64
     }
 }
 
 impl PartialEq for Temperature {
     fn eq(&self, other: &Temperature) -> bool {
-        self.val() == other.as_unit(self.unit()).val()
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Temperature {}
+
+impl Hash for Temperature {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
     }
 }
 
 impl PartialOrd for Temperature {
     fn partial_cmp(&self, other: &Temperature) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Temperature {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.val().cmp(&other.as_unit(self.unit()).val())
+        self.key().cmp(&other.key())
     }
 }
 
+// по умолчанию без дробной части, точность задаётся форматом: {:.1}
 impl Display for Temperature {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}", self.0, self.1)
+        let precision = f.precision().unwrap_or(0);
+        let val = Rounding::Nearest.apply(self.0, precision as u8);
+        write!(f, "{:.*}{}", precision, val, self.1)
     }
 }
 
@@ -89,49 +157,56 @@ mod tests {
         use Unit::*;
 
         assert_eq!(
-            Temperature(0, Celsius).as_unit(Fahrenheit),
-            Temperature(32, Fahrenheit)
+            Temperature(0.0, Celsius).as_unit(Fahrenheit),
+            Temperature(32.0, Fahrenheit)
         );
 
         assert_eq!(
-            Temperature(-50, Celsius).as_unit(Kelvin),
-            Temperature(223.15 as i16, Kelvin)
+            Temperature(-50.0, Celsius).as_unit(Kelvin),
+            Temperature(223.15, Kelvin)
         );
 
         assert_eq!(
-            Temperature(32, Fahrenheit).as_unit(Celsius),
-            Temperature(0, Celsius)
+            Temperature(32.0, Fahrenheit).as_unit(Celsius),
+            Temperature(0.0, Celsius)
         );
 
         assert_eq!(
-            Temperature(-459.67 as i16, Fahrenheit).as_unit(Kelvin),
-            Temperature(0, Kelvin)
+            Temperature(-459.67, Fahrenheit).as_unit(Kelvin),
+            Temperature(0.0, Kelvin)
         );
 
         assert_eq!(
-            Temperature(223.15 as i16, Kelvin).as_unit(Celsius),
-            Temperature(-50, Celsius)
+            Temperature(223.15, Kelvin).as_unit(Celsius),
+            Temperature(-50.0, Celsius)
         );
 
         assert_eq!(
-            Temperature(0, Kelvin).as_unit(Fahrenheit),
-            Temperature(-459.67 as i16, Fahrenheit)
+            Temperature(0.0, Kelvin).as_unit(Fahrenheit),
+            Temperature(-459.67, Fahrenheit)
         );
 
         assert_ne!(
-            Temperature(5, Celsius),
-            Temperature(4, Celsius)
-        )
+            Temperature(5.0, Celsius),
+            Temperature(4.0, Celsius)
+        );
+
+        // раньше 1°C -> 33°F -> 0°C
+        assert_eq!(
+            Temperature(1.0, Celsius).as_unit(Fahrenheit).as_unit(Celsius),
+            Temperature(1.0, Celsius)
+        );
     }
 
     #[test]
     fn unit_cmp() {
         use Unit::*;
-        assert!(Temperature(0, Celsius) <= Temperature(0, Celsius));
-        assert!(!(Temperature(0, Celsius) < Temperature(0, Celsius)));
+        assert!(Temperature(0.0, Celsius) <= Temperature(0.0, Celsius));
+        assert!(!(Temperature(0.0, Celsius) < Temperature(0.0, Celsius)));
 
-        assert!(Temperature(1, Celsius) > Temperature(30, Fahrenheit));
-        assert!(Temperature(100, Kelvin) < Temperature(25, Celsius));
+        assert!(Temperature(1.0, Celsius) > Temperature(30.0, Fahrenheit));
+        assert!(Temperature(100.0, Kelvin) < Temperature(25.0, Celsius));
+        assert_eq!(Temperature(0.0, Celsius), Temperature(32.0, Fahrenheit));
     }
 
     #[test]
@@ -148,5 +223,60 @@ mod tests {
         assert_eq!(format!("{}", Temperature::new(10, Celsius)), "10°C");
         assert_eq!(format!("{}", Temperature::new(247, Fahrenheit)), "247°F");
         assert_eq!(format!("{}", Temperature::new(90, Kelvin)), "90K");
+        assert_eq!(format!("{}", Temperature::new(3.6, Celsius)), "4°C");
+        assert_eq!(format!("{:.1}", Temperature::new(3.64, Celsius)), "3.6°C");
+        assert_eq!(format!("{}", Temperature::new(-0.4, Celsius)), "0°C");
+    }
+
+    #[test]
+    fn rounding() {
+        use Unit::*;
+        let t = Temperature::new(-2.45, Celsius);
+        assert_eq!(t.round(1, Rounding::Nearest).val(), -2.5);
+        assert_eq!(t.round(0, Rounding::Floor).val(), -3.0);
+        assert_eq!(t.round(0, Rounding::Ceil).val(), -2.0);
+        assert_eq!(t.round(1, Rounding::Trunc).val(), -2.4);
+        assert_eq!("floor".parse::<Rounding>(), Ok(Rounding::Floor));
+        assert!("up".parse::<Rounding>().is_err());
+    }
+
+    mod prop {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        fn unit() -> impl Strategy<Value=Unit> {
+            prop_oneof![Just(Unit::Celsius), Just(Unit::Fahrenheit), Just(Unit::Kelvin)]
+        }
+
+        proptest! {
+            #[test]
+            fn round_trip(val in -1000.0f64..1000.0, from in unit(), to in unit()) {
+                let t = Temperature::new(val, from);
+                let back = t.as_unit(to).as_unit(from);
+                prop_assert_eq!(back, t);
+                prop_assert!((back.val() - val).abs() < 1e-9);
+            }
+
+            #[test]
+            fn order_is_unit_independent(a in -500.0f64..500.0, b in -500.0f64..500.0, ua in unit(), ub in unit(), to in unit()) {
+                let ta = Temperature::new(a, ua);
+                let tb = Temperature::new(b, ub);
+                prop_assert_eq!(ta.cmp(&tb), ta.as_unit(to).cmp(&tb.as_unit(to)));
+                prop_assert_eq!(ta == tb, ta.cmp(&tb) == Ordering::Equal);
+            }
+
+            #[test]
+            fn rounding_bounds(val in -1000.0f64..1000.0, precision in 0u8..3) {
+                let step = 10f64.powi(-(precision as i32));
+                let t = Temperature::new(val, Unit::Celsius);
+                let floor = t.round(precision, Rounding::Floor).val();
+                let ceil = t.round(precision, Rounding::Ceil).val();
+                let nearest = t.round(precision, Rounding::Nearest).val();
+                prop_assert!(floor <= val + 1e-9 && val - 1e-9 <= ceil);
+                prop_assert!(ceil - floor <= step + 1e-9);
+                prop_assert!((nearest - val).abs() <= step / 2.0 + 1e-9);
+            }
+        }
     }
 }
@@ -0,0 +1,415 @@
+use chrono::NaiveDate;
+use handlebars::{Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError, Renderable};
+use serde_json::Value;
+
+use crate::locale::Locale;
+use crate::output::polybar;
+use crate::temperature::{Rounding, Temperature, Unit};
+use crate::units::Units;
+use crate::weather::icons::{IconSet, Icons};
+use crate::weather::weather::{Condition, Daytime};
+
+// Хелперы форматирования погоды, см. README "Template helpers"
+pub fn register(reg: &mut Handlebars, units: Units, icons: &Icons, locale: Locale) {
+    reg.register_helper("temp", Box::new(TempHelper { units }));
+    reg.register_helper("round", Box::new(round));
+    reg.register_helper("pad", Box::new(pad));
+    reg.register_helper("truncate", Box::new(truncate));
+    reg.register_helper("icon", Box::new(IconHelper { icons: icons.clone() }));
+    reg.register_helper("format_date", Box::new(FormatDate { locale }));
+    reg.register_helper("color", Box::new(color));
+    reg.register_helper("trend", Box::new(trend));
+    reg.register_helper("if_gt", Box::new(Compare { greater: true }));
+    reg.register_helper("if_lt", Box::new(Compare { greater: false }));
+    reg.register_helper("fg", Box::new(PolybarTag { tag: 'F' }));
+    reg.register_helper("bg", Box::new(PolybarTag { tag: 'B' }));
+    reg.register_helper("font", Box::new(PolybarTag { tag: 'T' }));
+    reg.register_helper("action", Box::new(action));
+}
+
+// {{ temp current.temperature unit="F" precision=1 rounding="floor" symbol=false }}
+// Параметр - объект температуры контекста, либо число в единицах units.
+#[derive(Clone, Copy)]
+struct TempHelper {
+    units: Units,
+}
+
+impl HelperDef for TempHelper {
+    fn call<'reg: 'rc, 'rc>(&self, h: &Helper<'reg, 'rc>, r: &'reg Handlebars<'reg>,
+                            _: &'rc Context, _: &mut RenderContext<'reg, 'rc>,
+                            out: &mut dyn Output) -> HelperResult {
+        check_params(h, r)?;
+        let t = match param(h, 0) {
+            Value::Object(obj) => obj.get("celsius_raw").and_then(Value::as_f64)
+                .map(|val| Temperature::new(val, Unit::Celsius)),
+            value => value.as_f64().map(|val| Temperature::new(val, self.units.temperature)),
+        };
+        let t = match t {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+        let unit = match hash_str(h, "unit") {
+            Some(unit) => parse_unit(unit).ok_or_else(|| RenderError::new(format!("unknown unit {}", unit)))?,
+            None => self.units.temperature,
+        };
+        let precision = hash_u64(h, "precision")?.unwrap_or(0) as u8;
+        let rounding: Rounding = match hash_str(h, "rounding") {
+            Some(rounding) => rounding.parse().map_err(RenderError::new)?,
+            None => Rounding::default(),
+        };
+        let t = t.as_unit(unit).round(precision, rounding);
+        let text = match h.hash_get("symbol").map(|v| v.value()) {
+            Some(Value::Bool(false)) => format!("{:.*}", precision as usize, t.val()),
+            _ => format!("{:.*}", precision as usize, t),
+        };
+        out.write(&text)?;
+        Ok(())
+    }
+}
+
+// {{ round current.wind.speed_ms 1 }}
+fn round(h: &Helper, r: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
+    check_params(h, r)?;
+    let digits = param_u64(h, 1)?.unwrap_or(0) as usize;
+    if let Some(value) = number(param(h, 0)) {
+        out.write(&format!("{:.*}", digits, Rounding::Nearest.apply(value, digits as u8)))?;
+    }
+    Ok(())
+}
+
+// Текст хелперов экранируется как {{ value }}: %% для polybar
+
+// {{ pad temperature 4 }} - выравнивание по правому краю до ширины 4,
+// align="left" - по левому, char="0" - символ заполнения
+fn pad(h: &Helper, r: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
+    check_params(h, r)?;
+    let text = text(param(h, 0));
+    let width = param_u64(h, 1)?.ok_or_else(|| RenderError::new("pad: width is required"))? as usize;
+    let fill = hash_str(h, "char").and_then(|s| s.chars().next()).unwrap_or(' ');
+    let padding = fill.to_string().repeat(width.saturating_sub(text.chars().count()));
+    let text = match hash_str(h, "align") {
+        Some("left") => text + &padding,
+        _ => padding + &text,
+    };
+    out.write(&r.get_escape_fn()(&text))?;
+    Ok(())
+}
+
+// {{ truncate condition 8 }} - не длиннее 8 символов, ellipsis="…" - признак обрезки
+fn truncate(h: &Helper, r: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
+    check_params(h, r)?;
+    let text = text(param(h, 0));
+    let width = param_u64(h, 1)?.ok_or_else(|| RenderError::new("truncate: width is required"))? as usize;
+    let ellipsis = hash_str(h, "ellipsis").unwrap_or("");
+    if text.chars().count() <= width {
+        out.write(&r.get_escape_fn()(&text))?;
+        return Ok(());
+    }
+    let keep = width.saturating_sub(ellipsis.chars().count());
+    let text: String = text.chars().take(keep).chain(ellipsis.chars()).take(width).collect();
+    out.write(&r.get_escape_fn()(&text))?;
+    Ok(())
+}
+
+// {{ icon current.condition_code current.daytime set="emoji" }}, по умолчанию день и иконки из [icons].
+// С set - иконка набора без своих иконок.
+struct IconHelper {
+    icons: Icons,
+}
+
+impl HelperDef for IconHelper {
+    fn call<'reg: 'rc, 'rc>(&self, h: &Helper<'reg, 'rc>, r: &'reg Handlebars<'reg>,
+                            _: &'rc Context, _: &mut RenderContext<'reg, 'rc>,
+                            out: &mut dyn Output) -> HelperResult {
+        check_params(h, r)?;
+        let condition: Condition = match serde_json::from_value(param(h, 0).clone()) {
+            Ok(condition) => condition,
+            Err(_) => return Ok(()),
+        };
+        let daytime: Daytime = serde_json::from_value(param(h, 1).clone()).unwrap_or(Daytime::Day);
+        let icon = match hash_str(h, "set") {
+            Some(set) => set.parse::<IconSet>().map_err(RenderError::new)?.icon(condition, daytime),
+            None => self.icons.icon(condition, daytime),
+        };
+        out.write(&r.get_escape_fn()(&icon))?;
+        Ok(())
+    }
+}
+
+// {{ format_date date "%a, %e %B" }} - дата дня прогноза (2022-03-28) в формате chrono,
+// названия дней и месяцев на языке locale
+#[derive(Clone, Copy)]
+struct FormatDate {
+    locale: Locale,
+}
+
+impl HelperDef for FormatDate {
+    fn call<'reg: 'rc, 'rc>(&self, h: &Helper<'reg, 'rc>, r: &'reg Handlebars<'reg>,
+                            _: &'rc Context, _: &mut RenderContext<'reg, 'rc>,
+                            out: &mut dyn Output) -> HelperResult {
+        check_params(h, r)?;
+        let date = match param(h, 0).as_str().and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()) {
+            Some(date) => date,
+            None => return Ok(()),
+        };
+        let fmt = param(h, 1).as_str().unwrap_or("%a, %e %B");
+        out.write(&date.format(&self.locale.date_format(fmt, &date)).to_string())?;
+        Ok(())
+    }
+}
+
+// {{ color temperature "#5e81ac" 0 "#a3be8c" 25 "#bf616a" }} - цвет по порогам:
+// ниже 0 - первый, от 0 до 25 - второй, от 25 - третий
+fn color(h: &Helper, r: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
+    check_params(h, r)?;
+    let value = match number(param(h, 0)) {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    let steps: Vec<&Value> = h.params().iter().skip(1).map(|p| p.value()).collect();
+    let (mut color, steps) = steps.split_first()
+        .ok_or_else(|| RenderError::new("color: expected colors separated by thresholds"))?;
+    for step in steps.chunks(2) {
+        if step.len() < 2 {
+            return Err(RenderError::new("color: expected colors separated by thresholds"));
+        }
+        let threshold = number(step[0]).ok_or_else(|| RenderError::new("color: threshold must be a number"))?;
+        if value < threshold {
+            break;
+        }
+        color = &step[1];
+    }
+    out.write(&text(color))?;
+    Ok(())
+}
+
+// {{ trend current.temperature forecast.parts.0.temperature }} - ↑, ↓ или → для второго значения
+// относительно первого; threshold=1 - минимальная разница, up, down, same - свои символы
+fn trend(h: &Helper, r: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
+    check_params(h, r)?;
+    let (from, to) = match (number(param(h, 0)), number(param(h, 1))) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Ok(()),
+    };
+    let threshold = match h.hash_get("threshold") {
+        Some(v) => number(v.value()).ok_or_else(|| RenderError::new("trend: threshold must be a number"))?,
+        None => 0.5,
+    };
+    let (key, default) = if to - from >= threshold {
+        ("up", "↑")
+    } else if from - to >= threshold {
+        ("down", "↓")
+    } else {
+        ("same", "→")
+    };
+    out.write(hash_str(h, key).unwrap_or(default))?;
+    Ok(())
+}
+
+// {{#if_gt current.temperature 25}}жарко{{else}}...{{/if_gt}}, {{#if_lt ...}}.
+// Без блока выводит "true", для подвыражений: {{#if (if_gt a b)}}
+#[derive(Clone, Copy)]
+struct Compare {
+    greater: bool,
+}
+
+impl HelperDef for Compare {
+    fn call<'reg: 'rc, 'rc>(&self, h: &Helper<'reg, 'rc>, r: &'reg Handlebars<'reg>,
+                            ctx: &'rc Context, rc: &mut RenderContext<'reg, 'rc>,
+                            out: &mut dyn Output) -> HelperResult {
+        check_params(h, r)?;
+        let result = match (number(param(h, 0)), number(param(h, 1))) {
+            (Some(a), Some(b)) if self.greater => a > b,
+            (Some(a), Some(b)) => a < b,
+            _ => false,
+        };
+        if h.template().is_none() {
+            if result {
+                out.write("true")?;
+            }
+            return Ok(());
+        }
+        match if result { h.template() } else { h.inverse() } {
+            Some(t) => t.render(r, ctx, rc, out),
+            None => Ok(()),
+        }
+    }
+}
+
+// Теги polybar: {{#fg "#bf616a"}}...{{/fg}} - %{F#bf616a}...%{F-}, bg - %{B...}, {{#font 3}} - %{T3}.
+// Пустое значение - без тега, например цвет из (color ...) без подходящего порога.
+#[derive(Clone, Copy)]
+struct PolybarTag {
+    tag: char,
+}
+
+impl HelperDef for PolybarTag {
+    fn call<'reg: 'rc, 'rc>(&self, h: &Helper<'reg, 'rc>, r: &'reg Handlebars<'reg>,
+                            ctx: &'rc Context, rc: &mut RenderContext<'reg, 'rc>,
+                            out: &mut dyn Output) -> HelperResult {
+        check_params(h, r)?;
+        let value = text(param(h, 0));
+        if !value.is_empty() {
+            out.write(&format!("%{{{}{}}}", self.tag, value))?;
+        }
+        if let Some(t) = h.template() {
+            t.render(r, ctx, rc, out)?;
+        }
+        if !value.is_empty() {
+            out.write(&format!("%{{{}-}}", self.tag))?;
+        }
+        Ok(())
+    }
+}
+
+// {{#action "xdg-open https://yandex.ru/pogoda" button=3}}...{{/action}} - %{A3:...:}...%{A},
+// по умолчанию левая кнопка
+fn action<'reg, 'rc>(h: &Helper<'reg, 'rc>, r: &'reg Handlebars<'reg>, ctx: &'rc Context,
+                     rc: &mut RenderContext<'reg, 'rc>, out: &mut dyn Output) -> HelperResult {
+    check_params(h, r)?;
+    let command = param(h, 0).as_str()
+        .ok_or_else(|| RenderError::new("action: expected a command"))?;
+    let button = hash_u64(h, "button")?.unwrap_or(1);
+    if !(1..=8).contains(&button) {
+        return Err(RenderError::new("action: button must be from 1 to 8"));
+    }
+    out.write(&polybar::action(button as u8, command))?;
+    if let Some(t) = h.template() {
+        t.render(r, ctx, rc, out)?;
+    }
+    out.write("%{A}")?;
+    Ok(())
+}
+
+const NULL: Value = Value::Null;
+
+// в строгом режиме отсутствующее значение параметра - ошибка, как и для {{ переменной }}
+fn check_params(h: &Helper, r: &Handlebars) -> Result<(), RenderError> {
+    if !r.strict_mode() {
+        return Ok(());
+    }
+    match h.params().iter().chain(h.hash().values()).find(|p| p.is_value_missing()) {
+        Some(p) => Err(RenderError::strict_error(p.relative_path())),
+        None => Ok(()),
+    }
+}
+
+fn param<'a>(h: &'a Helper, i: usize) -> &'a Value {
+    h.param(i).map(|p| p.value()).unwrap_or(&NULL)
+}
+
+fn param_u64(h: &Helper, i: usize) -> Result<Option<u64>, RenderError> {
+    match h.param(i) {
+        Some(p) => p.value().as_u64().map(Some)
+            .ok_or_else(|| RenderError::new(format!("{}: parameter {} must be a number", h.name(), i + 1))),
+        None => Ok(None),
+    }
+}
+
+fn hash_str<'a>(h: &'a Helper, key: &str) -> Option<&'a str> {
+    h.hash_get(key).and_then(|v| v.value().as_str())
+}
+
+fn hash_u64(h: &Helper, key: &str) -> Result<Option<u64>, RenderError> {
+    match h.hash_get(key) {
+        Some(v) => v.value().as_u64().map(Some)
+            .ok_or_else(|| RenderError::new(format!("{} must be a number", key))),
+        None => Ok(None),
+    }
+}
+
+// число, строка с числом или объект температуры (значение в единицах units)
+fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Object(obj) => obj.get("raw").and_then(Value::as_f64),
+        _ => None,
+    }
+}
+
+fn text(v: &Value) -> String {
+    match v {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+fn parse_unit(s: &str) -> Option<Unit> {
+    match s.to_lowercase().as_str() {
+        "c" | "celsius" => Some(Unit::Celsius),
+        "f" | "fahrenheit" => Some(Unit::Fahrenheit),
+        "k" | "kelvin" => Some(Unit::Kelvin),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::template::template::tests::render;
+
+    #[test]
+    fn temp() {
+        assert_eq!(render("{{ temp current.temperature }}"), "4°C");
+        assert_eq!(render("{{ temp current.temperature unit=\"F\" precision=1 }}"), "38.5°F");
+        assert_eq!(render("{{ temp temperature_raw rounding=\"floor\" symbol=false }}"), "3");
+        assert_eq!(render("{{ temp current.feel_temperature }}"), "");
+    }
+
+    #[test]
+    fn round() {
+        assert_eq!(render("{{ round current.temperature.raw }} {{ round current.wind.speed_ms 2 }}"), "4 4.10");
+        assert_eq!(render("{{ round forecast.parts.1.temperature 1 }}"), "-0.5");
+    }
+
+    #[test]
+    fn pad_and_truncate() {
+        assert_eq!(render("[{{ pad current.temperature.value 3 }}]"), "[  4]");
+        assert_eq!(render("[{{ pad humidity 4 align=\"left\" char=\"_\" }}]"), "[65__]");
+        assert_eq!(render("[{{ truncate current.condition 4 }}]"), "[clou]");
+        assert_eq!(render("[{{ truncate current.condition 5 ellipsis=\"…\" }}]"), "[clou…]");
+        assert_eq!(render("[{{ truncate current.condition 10 ellipsis=\"…\" }}]"), "[cloudy]");
+    }
+
+    #[test]
+    fn icon() {
+        assert_eq!(render("{{ icon current.condition_code current.daytime set=\"emoji\" }}"), "🌥");
+        assert_eq!(render("{{ icon forecast.parts.0.condition_code forecast.parts.0.daytime set=\"emoji\" }}"), "☁");
+        assert_eq!(render("{{ icon current.condition_code }}"), render("{{ condition_icon }}"));
+        assert_eq!(render("{{ icon current.missing }}"), "");
+    }
+
+    #[test]
+    fn color() {
+        let template = |value: &str| format!("{{{{ color {} \"blue\" 0 \"green\" 5 \"red\" }}}}", value);
+        assert_eq!(render(&template("current.temperature")), "green");
+        assert_eq!(render(&template("forecast.parts.1.temperature")), "blue");
+        assert_eq!(render(&template("forecast.daily.0.temperature_max")), "red");
+        assert_eq!(render(&template("5")), "red");
+    }
+
+    #[test]
+    fn trend() {
+        assert_eq!(render("{{ trend current.temperature forecast.parts.0.temperature }}"), "↓");
+        assert_eq!(render("{{ trend forecast.parts.1.temperature current.temperature up=\"+\" }}"), "+");
+        assert_eq!(render("{{ trend current.temperature current.temperature.raw }}"), "→");
+        assert_eq!(render("{{ trend current.temperature 3.4 threshold=1 }}"), "→");
+    }
+
+    #[test]
+    fn compare() {
+        assert_eq!(render("{{#if_gt current.temperature 3}}warm{{else}}cold{{/if_gt}}"), "warm");
+        assert_eq!(render("{{#if_lt current.temperature 3}}cold{{else}}warm{{/if_lt}}"), "warm");
+        assert_eq!(render("{{#each forecast.parts}}{{#if_lt temperature 0}}{{ name }}{{/if_lt}}{{/each}}"), "night");
+        assert_eq!(render("{{#if (if_gt humidity 60)}}wet{{/if}}"), "wet");
+    }
+
+    #[test]
+    fn polybar_tags() {
+        assert_eq!(render("{{#fg (color current.temperature \"#5e81ac\" 5 \"#bf616a\")}}{{#font 3}}x{{/font}}{{/fg}}"),
+                   "%{F#5e81ac}%{T3}x%{T-}%{F-}");
+        assert_eq!(render("{{#bg \"\"}}x{{/bg}}"), "x");
+        assert_eq!(render("{{#action \"notify-send a:b\" button=3}}x{{/action}}"), "%{A3:notify-send a\\:b:}x%{A}");
+    }
+}
@@ -1 +1,4 @@
-pub mod template;
\ No newline at end of file
+pub mod template;
+pub mod helpers;
+pub mod templates;
+pub mod sample;
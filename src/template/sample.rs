@@ -0,0 +1,97 @@
+use std::time::{Duration, SystemTime};
+
+use crate::config::config::Provider;
+use crate::temperature::Temperature;
+use crate::temperature::Unit::Celsius;
+use crate::weather::weather::{Alert, Astro, Condition, Daytime, Forecast, ForecastDay, ForecastPart, MoonPhase,
+                              PART_NAMES, Precipitation, Pressure, WeatherInfo, Wind, WindDirection};
+
+// дней прогноза в проверочных данных
+const SAMPLE_DAYS: usize = 7;
+
+// Полностью заполненные данные для проверки шаблонов: любое поле, которое может вернуть провайдер,
+// есть в контексте. providers - данные каждого из провайдеров без прогноза по дням.
+pub fn weather(providers: &[Provider]) -> WeatherInfo {
+    WeatherInfo {
+        sources: providers.iter()
+            .map(|provider| {
+                let mut w = current(provider.name());
+                w.forecasts = Some(Forecast { parts: parts(), days: vec![] });
+                w
+            })
+            .collect(),
+        forecasts: Some(Forecast {
+            parts: parts(),
+            days: (0..SAMPLE_DAYS).map(day).collect(),
+        }),
+        alerts: Some(vec![Alert {
+            event: "wind".to_string(),
+            title: "Strong wind".to_string(),
+            description: Some("Gusts up to 25 m/s".to_string()),
+            severity: Some("Moderate".to_string()),
+            start: Some(SystemTime::now()),
+            end: Some(SystemTime::now() + Duration::from_secs(3600)),
+        }]),
+        ..current("sample")
+    }
+}
+
+fn current(provider: &str) -> WeatherInfo {
+    let now = SystemTime::now();
+    WeatherInfo {
+        provider: Some(provider.to_string()),
+        temp: Some(Temperature::new(3.6, Celsius)),
+        feels_like: Some(Temperature::new(-0.4, Celsius)),
+        humidity: Some(65),
+        icon: Some("ovc".to_string()),
+        icon_url: Some("https://example.com/ovc.png".to_string()),
+        condition: Some(Condition::Cloudy),
+        daytime: Some(Daytime::Day),
+        wind: Some(wind()),
+        pressure: Some(Pressure(1012.0)),
+        precipitation: Some(Precipitation { mm: Some(0.3), prob: Some(40) }),
+        astro: Some(Astro {
+            sunrise: Some(now - Duration::from_secs(6 * 3600)),
+            sunset: Some(now + Duration::from_secs(6 * 3600)),
+            moon_phase: Some(MoonPhase::FullMoon),
+        }),
+        ..Default::default()
+    }
+}
+
+fn day(i: usize) -> ForecastDay {
+    let mut day = ForecastDay::new(&format!("2022-04-{:02}", i + 1));
+    day.temp_min = Some(Temperature::new(-2, Celsius));
+    day.temp_max = Some(Temperature::new(7, Celsius));
+    day.humidity = Some(80);
+    day.icon = Some("ovc".to_string());
+    day.icon_url = Some("https://example.com/ovc.png".to_string());
+    day.condition = Some(Condition::Rain);
+    day.parts = parts();
+    day.hours = (0..24).map(|hour| part(&hour.to_string())).collect();
+    day
+}
+
+fn parts() -> Vec<ForecastPart> {
+    PART_NAMES.iter().map(|name| part(name)).collect()
+}
+
+fn part(name: &str) -> ForecastPart {
+    ForecastPart {
+        name: name.to_string(),
+        temp: Temperature::new(1.5, Celsius),
+        humidity: Some(90),
+        icon: Some("ovc".to_string()),
+        icon_url: Some("https://example.com/ovc.png".to_string()),
+        condition: Some(Condition::LightSnow),
+        feels_like: Some(Temperature::new(-2.5, Celsius)),
+        daytime: Some(Daytime::Night),
+        wind: Some(wind()),
+        pressure: Some(Pressure(1010.0)),
+        precipitation: Some(Precipitation { mm: Some(1.2), prob: Some(70) }),
+    }
+}
+
+fn wind() -> Wind {
+    Wind { speed: 4.1, gust: Some(7.5), dir: Some(WindDirection::S) }
+}
@@ -1,26 +1,35 @@
 extern crate chrono;
 
-use std::collections::HashMap;
-use std::time::SystemTime;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use chrono::{DateTime, Local};
-use handlebars::{Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext};
+use chrono::{Local, TimeZone};
+use handlebars::{Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError};
 use serde::{Serialize, Serializer};
-use serde::ser::SerializeStruct;
+use serde::ser::SerializeMap;
+use serde_json::{Map, Value};
 
 use crate::Error;
-use crate::temperature::Temperature;
+use crate::template::helpers;
+use crate::temperature::{Rounding, Temperature, Unit};
 use crate::temperature::Unit::*;
-use crate::weather::weather::{Condition, Daytime, Forecast, ForecastPart, WeatherInfo};
+use crate::locale::Locale;
+use crate::units::Units;
+use crate::weather::icons::Icons;
+use crate::weather::weather::{Condition, Daytime, ForecastPart, MoonPhase, Precipitation, Pressure, WeatherInfo, Wind};
 
 const TEMPLATE_DEBUG: &str = r#"
 Weather template variables:
 
     cache: {{ cache }}
+    provider: {{ provider }}
+    providers: {{#each providers}}{{ @key }}={{ this.temperature_celsius_full }} {{/each}}
 
     created format="%H:%M": {{ created format="%H:%M "}}
     created: {{ created }}
 
+    temperature: {{ temperature }}
+    temperature_full: {{ temperature_full }}
     temperature_celsius: {{ temperature_celsius }}
     temperature_celsius_full: {{ temperature_celsius_full }}
     temperature_kelvin: {{ temperature_kelvin }}
@@ -39,6 +48,24 @@ Weather template variables:
     condition_icon: {{ condition_icon }}
     icon: {{ icon }}
     icon_url: {{ icon_url }}
+    wind_speed_full: {{ wind_speed_full }}
+    wind_speed_ms: {{ wind_speed_ms }}
+    wind_speed_kmh: {{ wind_speed_kmh }}
+    wind_gust_ms: {{ wind_gust_ms }}
+    wind_dir: {{ wind_dir }}
+    wind_dir_arrow: {{ wind_dir_arrow }}
+    pressure_full: {{ pressure_full }}
+    pressure_hpa: {{ pressure_hpa }}
+    pressure_mmhg: {{ pressure_mmhg }}
+    pressure_inhg: {{ pressure_inhg }}
+    precipitation_full: {{ precipitation_full }}
+    precipitation_mm: {{ precipitation_mm }}
+    precipitation_prob: {{ precipitation_prob }}
+    sunrise format="%H:%M": {{ sunrise format="%H:%M" }}
+    sunset format="%H:%M": {{ sunset format="%H:%M" }}
+    day_length: {{ day_length }}
+    moon_phase: {{ moon_phase }}
+    moon_phase_icon: {{ moon_phase_icon }}
 
 
 
@@ -62,6 +89,9 @@ Weather template variables:
     forecast_0_condition_icon: {{ forecast_0_condition_icon }}
     forecast_0_icon: {{ forecast_0_icon }}
     forecast_0_icon_url: {{ forecast_0_icon_url }}
+    forecast_0_wind_speed_ms: {{ forecast_0_wind_speed_ms }}
+    forecast_0_pressure_mmhg: {{ forecast_0_pressure_mmhg }}
+    forecast_0_precipitation_prob: {{ forecast_0_precipitation_prob }}
 
     forecast_1_name: {{ forecast_1_name }}
     forecast_1_temperature_celsius: {{ forecast_1_temperature_celsius }}
@@ -83,197 +113,457 @@ Weather template variables:
     forecast_1_icon: {{ forecast_1_icon }}
     forecast_1_icon_url: {{ forecast_1_icon_url }}
 
+    forecast_days_count: {{ forecast_days_count }}
+    forecast_day_0_date: {{ forecast_day_0_date }}
+    forecast_day_0_temperature_min_celsius: {{ forecast_day_0_temperature_min_celsius }}
+    forecast_day_0_temperature_max_celsius: {{ forecast_day_0_temperature_max_celsius }}
+    forecast_day_0_humidity: {{ forecast_day_0_humidity }}
+    forecast_day_0_condition: {{ forecast_day_0_condition }}
+    forecast_day_0_condition_icon: {{ forecast_day_0_condition_icon }}
+    forecast_day_0_day_temperature_celsius: {{ forecast_day_0_day_temperature_celsius }}
+    forecast_day_0_hour_12_temperature_celsius: {{ forecast_day_0_hour_12_temperature_celsius }}
+
+    alerts_count: {{ alerts_count }}
+    alert_0_title: {{ alert_0_title }}
+    alert_0_severity: {{ alert_0_severity }}
+
+    current.temperature.full: {{ current.temperature.full }}
+    forecast.parts: {{#each forecast.parts}}{{ name }}={{ temperature.full }} {{/each}}
+    forecast.daily: {{#each forecast.daily}}{{ date }}={{ temperature_min.full }}..{{ temperature_max.full }} {{/each}}
+    alerts: {{#each alerts}}{{ title }} {{/each}}
+
      "#;
 
-struct WeatherInfoTemplate {
-    is_cached: bool,
-    created_at: SystemTime,
-    temp: Temperature,
-    feels_like: Option<Temperature>,
+// Контекст шаблона: current, astro, forecast.parts[], forecast.daily[], alerts[] и providers.<name>.
+// Плоские имена (temperature_celsius, forecast_0_name, ...) строятся из него функцией flat_names.
+#[derive(Serialize)]
+struct WeatherContext {
+    cached: bool,
+    provider: Option<String>,
+    #[serde(serialize_with = "unix_time")]
+    date: SystemTime,
+    current: Option<PartContext>,
+    astro: Option<AstroContext>,
+    forecast: Option<ForecastContext>,
+    alerts: Option<Vec<AlertContext>>,
+    providers: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize)]
+struct AlertContext {
+    event: String,
+    title: String,
+    description: Option<String>,
+    severity: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ForecastContext {
+    parts: Vec<PartContext>,
+    daily: Vec<DayContext>,
+}
+
+// текущая погода, часть суток или час. name - на языке locale, key - night, morning, ..., час
+#[derive(Serialize)]
+struct PartContext {
+    name: Option<String>,
+    key: Option<String>,
+    temperature: Option<TemperatureContext>,
+    feel_temperature: Option<TemperatureContext>,
     humidity: Option<u64>,
-    condition: Option<Condition>,
-    forecasts: Option<Forecast>,
+    condition: Option<String>,
+    condition_code: Option<Condition>,
+    condition_icon: Option<String>,
     icon: Option<String>,
+    icon_url: Option<String>,
     daytime: Option<Daytime>,
+    wind: Option<WindContext>,
+    pressure: Option<PressureContext>,
+    precipitation: Option<PrecipitationContext>,
 }
 
+#[derive(Serialize)]
+struct DayContext {
+    date: String,
+    temperature_min: Option<TemperatureContext>,
+    temperature_max: Option<TemperatureContext>,
+    humidity: Option<u64>,
+    condition: Option<String>,
+    condition_code: Option<Condition>,
+    condition_icon: Option<String>,
+    icon: Option<String>,
+    icon_url: Option<String>,
+    parts: Vec<PartContext>,
+    hours: Vec<PartContext>,
+}
 
-impl WeatherInfoTemplate {
-    fn from(w: &WeatherInfo) -> Self {
-        let mut f: Option<Forecast> = None;
-        if let Some(forecast) = &w.forecasts {
-            let mut fv = Forecast {
-                parts: vec![],
-            };
-            for p in forecast.parts.iter() {
-                fv.parts.push(ForecastPart {
-                    name: p.name.to_string(),
-                    condition: p.condition,
-                    feels_like: p.feels_like,
-                    temp: p.temp,
-                    humidity: p.humidity,
-                    icon: p.icon.clone(),
-                    daytime: p.daytime,
-                });
-            }
-            f = Some(fv)
+#[derive(Serialize)]
+struct AstroContext {
+    #[serde(serialize_with = "unix_time_opt")]
+    sunrise: Option<SystemTime>,
+    #[serde(serialize_with = "unix_time_opt")]
+    sunset: Option<SystemTime>,
+    day_length: Option<String>,
+    moon_phase: Option<&'static str>,
+    moon_phase_code: Option<MoonPhase>,
+    moon_phase_icon: Option<char>,
+}
+
+#[derive(Serialize)]
+struct WindContext {
+    speed: f64,
+    speed_full: String,
+    speed_ms: f64,
+    speed_kmh: i64,
+    gust: Option<f64>,
+    gust_full: Option<String>,
+    gust_ms: Option<f64>,
+    dir: Option<&'static str>,
+    dir_arrow: Option<char>,
+}
+
+#[derive(Serialize)]
+struct PressureContext {
+    value: Value,
+    full: String,
+    hpa: i64,
+    mmhg: i64,
+    inhg: f64,
+}
+
+#[derive(Serialize)]
+struct PrecipitationContext {
+    value: Option<f64>,
+    full: Option<String>,
+    mm: Option<f64>,
+    prob: Option<u64>,
+}
+
+// value, full в единицах units и celsius, celsius_full и т.д. для всех единиц,
+// raw, celsius_raw - без округления, для {{ temperature_celsius precision=1 }}
+struct TemperatureContext {
+    t: Temperature,
+    units: Units,
+}
+
+impl Serialize for TemperatureContext {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer {
+        let mut s = serializer.serialize_map(Some(3 * (UNIT_NAMES.len() + 1)))?;
+        let fields = UNIT_NAMES.iter()
+            .map(|(unit, name)| (*unit, name.to_string(), format!("{}_full", name), format!("{}_raw", name)))
+            .chain([(self.units.temperature, "value".to_string(), "full".to_string(), "raw".to_string())]);
+        for (unit, value, full, raw) in fields {
+            let t = self.t.as_unit(unit);
+            s.serialize_entry(&value, &(Rounding::Nearest.apply(t.val(), 0) as i64))?;
+            s.serialize_entry(&full, &format!("{}", t))?;
+            s.serialize_entry(&raw, &t.val())?;
         }
+        s.end()
+    }
+}
 
-        WeatherInfoTemplate {
-            is_cached: w.is_cached,
-            created_at: w.created_at,
-            temp: w.temp,
-            feels_like: w.feels_like,
+impl WeatherContext {
+    fn from(w: &WeatherInfo, units: Units, icons: &Icons, locale: Locale) -> Self {
+        let temperature = |t: Option<Temperature>| t.map(|t| TemperatureContext { t, units });
+        let current = w.temp.map(|temp| PartContext {
+            name: None,
+            key: None,
+            temperature: temperature(Some(temp)),
+            feel_temperature: temperature(w.feels_like),
             humidity: w.humidity,
-            condition: w.condition,
-            forecasts: f,
+            condition: w.condition.map(|c| locale.condition(c)),
+            condition_code: w.condition,
+            condition_icon: w.condition.zip(w.daytime).map(|(c, daytime)| icons.icon(c, daytime)),
             icon: w.icon.clone(),
+            icon_url: w.icon_url.clone(),
             daytime: w.daytime,
+            wind: w.wind.map(|wind| WindContext::from(wind, &units)),
+            pressure: w.pressure.map(|pressure| PressureContext::from(pressure, &units)),
+            precipitation: w.precipitation.map(|p| PrecipitationContext::from(p, &units)),
+        });
+        let forecast = w.forecasts.as_ref().map(|forecast| ForecastContext {
+            parts: forecast.parts.iter().map(|part| PartContext::from(part, units, icons, locale)).collect(),
+            daily: forecast.days.iter().map(|day| DayContext {
+                date: day.date.clone(),
+                temperature_min: temperature(day.temp_min),
+                temperature_max: temperature(day.temp_max),
+                humidity: day.humidity,
+                condition: day.condition.map(|c| locale.condition(c)),
+                condition_code: day.condition,
+                condition_icon: day.condition.map(|c| icons.icon(c, Daytime::Day)),
+                icon: day.icon.clone(),
+                icon_url: day.icon_url.clone(),
+                parts: day.parts.iter().map(|part| PartContext::from(part, units, icons, locale)).collect(),
+                hours: day.hours.iter().map(|hour| PartContext::from(hour, units, icons, locale)).collect(),
+            }).collect(),
+        });
+        WeatherContext {
+            cached: w.is_cached,
+            provider: w.provider.clone(),
+            date: w.created_at,
+            current,
+            astro: w.astro.map(|astro| AstroContext {
+                sunrise: astro.sunrise,
+                sunset: astro.sunset,
+                day_length: astro.day_length().map(|d| {
+                    let minutes = d.as_secs() / 60;
+                    format!("{}:{:02}", minutes / 60, minutes % 60)
+                }),
+                moon_phase: astro.moon_phase.map(|phase| phase.name()),
+                moon_phase_code: astro.moon_phase,
+                moon_phase_icon: astro.moon_phase.map(|phase| phase.icon()),
+            }),
+            forecast,
+            alerts: w.alerts.as_ref().map(|alerts| alerts.iter().map(|alert| AlertContext {
+                event: alert.event.clone(),
+                title: alert.title.clone(),
+                description: alert.description.clone(),
+                severity: alert.severity.clone(),
+            }).collect()),
+            providers: w.sources.iter()
+                .filter_map(|source| Some((source.provider.clone()?, context(source, units, icons, locale))))
+                .collect(),
         }
     }
 }
 
-impl Serialize for WeatherInfoTemplate {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: Serializer {
-        let mut name_units = HashMap::new();
-        name_units.insert(Celsius, "celsius");
-        name_units.insert(Kelvin, "kelvin");
-        name_units.insert(Fahrenheit, "fahrenheit");
+impl PartContext {
+    fn from(part: &ForecastPart, units: Units, icons: &Icons, locale: Locale) -> Self {
+        let temperature = |t: Option<Temperature>| t.map(|t| TemperatureContext { t, units });
+        PartContext {
+            name: Some(locale.text(&part.name).to_string()),
+            key: Some(part.name.clone()),
+            temperature: temperature(Some(part.temp)),
+            feel_temperature: temperature(part.feels_like),
+            humidity: part.humidity,
+            condition: part.condition.map(|c| locale.condition(c)),
+            condition_code: part.condition,
+            condition_icon: part.condition.zip(part.daytime).map(|(c, daytime)| icons.icon(c, daytime)),
+            icon: part.icon.clone(),
+            icon_url: part.icon_url.clone(),
+            daytime: part.daytime,
+            wind: part.wind.map(|wind| WindContext::from(wind, &units)),
+            pressure: part.pressure.map(|pressure| PressureContext::from(pressure, &units)),
+            precipitation: part.precipitation.map(|p| PrecipitationContext::from(p, &units)),
+        }
+    }
+}
 
+impl WindContext {
+    fn from(wind: Wind, units: &Units) -> Self {
+        let speed = round(units.wind.convert(wind.speed), 1);
+        let gust = wind.gust.map(|gust| round(units.wind.convert(gust), 1));
+        WindContext {
+            speed,
+            speed_full: format!("{} {}", speed, units.wind),
+            speed_ms: round(wind.speed, 1),
+            speed_kmh: (wind.speed * 3.6).round() as i64,
+            gust,
+            gust_full: gust.map(|gust| format!("{} {}", gust, units.wind)),
+            gust_ms: wind.gust.map(|gust| round(gust, 1)),
+            dir: wind.dir.map(|dir| dir.name()),
+            dir_arrow: wind.dir.map(|dir| dir.arrow()),
+        }
+    }
+}
+
+impl PressureContext {
+    fn from(pressure: Pressure, units: &Units) -> Self {
+        let precision = units.pressure.precision();
+        let value = round(units.pressure.convert(pressure), precision as i32);
+        PressureContext {
+            value: rounded(value, precision),
+            full: format!("{:.*} {}", precision, value, units.pressure),
+            hpa: pressure.hpa().round() as i64,
+            mmhg: pressure.mm_hg().round() as i64,
+            inhg: round(pressure.in_hg(), 2),
+        }
+    }
+}
+
+impl PrecipitationContext {
+    fn from(precipitation: Precipitation, units: &Units) -> Self {
+        let precision = units.precipitation.precision();
+        let value = precipitation.mm.map(|mm| round(units.precipitation.convert(mm), precision as i32));
+        PrecipitationContext {
+            value,
+            full: value.map(|value| format!("{:.*} {}", precision, value, units.precipitation)),
+            mm: precipitation.mm.map(|mm| round(mm, 1)),
+            prob: precipitation.prob,
+        }
+    }
+}
 
-        let mut s = serializer.serialize_struct("WeatherInfo", 30)?;
+// вложенный контекст вместе с плоскими именами
+fn context(w: &WeatherInfo, units: Units, icons: &Icons, locale: Locale) -> Value {
+    let mut map = match nested_context(w, units, icons, locale) {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    for (name, value) in flat_names(&map) {
+        map.entry(name).or_insert(value);
+    }
+    Value::Object(map)
+}
 
-        s.serialize_field("cache", &format!("{}", self.is_cached))?;
+// Вложенный контекст без плоских имён, он же выводится --format json
+pub fn nested_context(w: &WeatherInfo, units: Units, icons: &Icons, locale: Locale) -> Value {
+    serde_json::to_value(WeatherContext::from(w, units, icons, locale))
+        .expect("failed to serialize template context")
+}
 
-        s.serialize_field("date", &self.created_at)?;
+// время в контексте - unix-время в секундах
+pub(crate) fn unix_time<S: Serializer>(t: &SystemTime, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default())
+}
 
-        for unit in vec![Celsius, Kelvin, Fahrenheit] {
-            let t_c = self.temp.as_unit(unit);
-            let name_field = format!("temperature_{}", name_units.get(&unit).unwrap());
-            let name_field_full = format!("temperature_{}_full", name_units.get(&unit).unwrap());
-            s.serialize_field(string_to_static_str(name_field), &t_c.val())?;
-            s.serialize_field(string_to_static_str(name_field_full), &format!("{}", t_c))?;
+pub(crate) fn unix_time_opt<S: Serializer>(t: &Option<SystemTime>, s: S) -> Result<S::Ok, S::Error> {
+    match t {
+        Some(t) => unix_time(t, s),
+        None => s.serialize_none(),
+    }
+}
 
-            if let Some(feel) = self.feels_like {
-                let t_c = feel.as_unit(unit);
-                let name_field = format!("feel_temperature_{}", name_units.get(&unit).unwrap());
-                let name_field_full = format!("feel_temperature_{}_full", name_units.get(&unit).unwrap());
-                s.serialize_field(string_to_static_str(name_field), &t_c.val())?;
-                s.serialize_field(string_to_static_str(name_field_full), &format!("{}", t_c))?;
+// Плоские имена прежних версий: temperature_celsius, wind_speed_ms, forecast_0_name,
+// forecast_night_temperature, forecast_day_0_hour_12_temperature_celsius, alert_0_title, ...
+fn flat_names(context: &Map<String, Value>) -> Map<String, Value> {
+    let mut flat = Map::new();
+    flat.insert("cache".to_string(), Value::String(context["cached"].to_string()));
+    flatten(&mut flat, "", &context["current"]);
+    if let Some(astro) = context["astro"].as_object() {
+        for (key, value) in astro {
+            // sunrise и sunset - хелперы, выводящие sunrise_date и sunset_date
+            match key.as_str() {
+                "sunrise" | "sunset" => {
+                    flat.insert(format!("{}_date", key), value.clone());
+                }
+                _ => flatten(&mut flat, key, value),
             }
         }
-        if let Some(humidity) = self.humidity {
-            s.serialize_field("humidity", &humidity)?;
-        }
-        if let Some(condition) = self.condition {
-            s.serialize_field("condition_code", &condition)?;
+    }
 
-            if let Some(daytime) = self.daytime {
-                let icon = condition.icon(daytime);
-                s.serialize_field("condition_icon", &icon)?;
+    let forecast = &context["forecast"];
+    if let Some(parts) = forecast["parts"].as_array() {
+        flat.insert("forecast_count".to_string(), parts.len().into());
+        for (i, part) in parts.iter().enumerate() {
+            flatten(&mut flat, &format!("forecast_{}", i), part);
+            flatten(&mut flat, &format!("forecast_{}", part["key"].as_str().unwrap_or_default()), part);
+        }
+    }
+    if let Some(days) = forecast["daily"].as_array() {
+        flat.insert("forecast_days_count".to_string(), days.len().into());
+        for (i, day) in days.iter().enumerate() {
+            let prefix = format!("forecast_day_{}", i);
+            flatten(&mut flat, &prefix, day);
+            for part in day["parts"].as_array().into_iter().flatten() {
+                flatten(&mut flat, &format!("{}_{}", prefix, part["key"].as_str().unwrap_or_default()), part);
+            }
+            for hour in day["hours"].as_array().into_iter().flatten() {
+                flatten(&mut flat, &format!("{}_hour_{}", prefix, hour["key"].as_str().unwrap_or_default()), hour);
             }
-            s.serialize_field("condition", &condition.name())?;
         }
+    }
 
-        if let Some(icon) = &self.icon {
-            s.serialize_field("icon", icon)?;
-            let value = format!("https://yastatic.net/weather/i/icons/funky/dark/{}.svg", *icon);
-            s.serialize_field("icon_url", &value)?;
+    if let Some(alerts) = context["alerts"].as_array() {
+        flat.insert("alerts_count".to_string(), alerts.len().into());
+        for (i, alert) in alerts.iter().enumerate() {
+            flatten(&mut flat, &format!("alert_{}", i), alert);
         }
+    }
+    flat
+}
 
-
-        if let Some(forecasts) = &self.forecasts {
-            s.serialize_field("forecast_count", &forecasts.parts.len())?;
-
-            for (i, part) in forecasts.parts.iter().enumerate() {
-                for unit in vec![Celsius, Kelvin, Fahrenheit] {
-                    let t_c = part.temp.as_unit(unit);
-
-                    let name_field = format!("forecast_{}_name", i);
-                    s.serialize_field(string_to_static_str(name_field), &part.name)?;
-
-                    let name_field = format!("forecast_{}_temperature_{}", i, name_units.get(&unit).unwrap());
-                    let name_field_full = format!("forecast_{}_temperature_{}_full", i, name_units.get(&unit).unwrap());
-                    s.serialize_field(string_to_static_str(name_field), &t_c.val())?;
-                    s.serialize_field(string_to_static_str(name_field_full), &format!("{}", t_c))?;
-
-                    let name_field = format!("forecast_{}_temperature_{}", part.name, name_units.get(&unit).unwrap());
-                    let name_field_full = format!("forecast_{}_temperature_{}_full", part.name, name_units.get(&unit).unwrap());
-                    s.serialize_field(string_to_static_str(name_field), &t_c.val())?;
-                    s.serialize_field(string_to_static_str(name_field_full), &format!("{}", t_c))?;
-
-                    if let Some(feel) = part.feels_like {
-                        let t_c = feel.as_unit(unit);
-                        let name_field = format!("forecast_{}_feel_temperature_{}", i, name_units.get(&unit).unwrap());
-                        let name_field_full = format!("forecast_{}_feel_temperature_{}_full", i, name_units.get(&unit).unwrap());
-                        s.serialize_field(string_to_static_str(name_field), &t_c.val())?;
-                        s.serialize_field(string_to_static_str(name_field_full), &format!("{}", t_c))?;
-
-                        let name_field = format!("forecast_{}_feel_temperature_{}", part.name, name_units.get(&unit).unwrap());
-                        let name_field_full = format!("forecast_{}_feel_temperature_{}_full", part.name, name_units.get(&unit).unwrap());
-                        s.serialize_field(string_to_static_str(name_field), &t_c.val())?;
-                        s.serialize_field(string_to_static_str(name_field_full), &format!("{}", t_c))?;
-                    }
-
-                    if let Some(humidity) = part.humidity {
-                        let name_field = format!("forecast_{}_humidity", i);
-                        s.serialize_field(string_to_static_str(name_field), &humidity)?;
-                        let name_field = format!("forecast_{}_humidity", part.name);
-                        s.serialize_field(string_to_static_str(name_field), &humidity)?;
-                    }
-
-                    if let Some(condition) = part.condition {
-                        let name_field = format!("forecast_{}_condition_code", i);
-                        s.serialize_field(string_to_static_str(name_field), &condition)?;
-
-                        if let Some(daytime) = part.daytime {
-                            let icon = condition.icon(daytime);
-                            let name_field = format!("forecast_{}_condition_icon", i);
-                            s.serialize_field(string_to_static_str(name_field), &icon)?;
-                        }
-
-                        let name_field = format!("forecast_{}_condition", i);
-                        s.serialize_field(string_to_static_str(name_field), &condition.name())?;
-                    }
-
-                    if let Some(icon) = &part.icon {
-                        let name_field = format!("forecast_{}_icon", i);
-                        s.serialize_field(string_to_static_str(name_field), icon)?;
-                        let value = format!("https://yastatic.net/weather/i/icons/funky/dark/{}.svg", *icon);
-                        let name_field = format!("forecast_{}_icon_url", i);
-                        s.serialize_field(string_to_static_str(name_field), &value)?;
-                    }
-                }
+// {"wind": {"speed": 1}} -> wind_speed, поле value получает имя родителя: {"pressure": {"value": 1}} -> pressure.
+// null и массивы пропускаются.
+fn flatten(flat: &mut Map<String, Value>, prefix: &str, value: &Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let name = match (prefix, key.as_str()) {
+                    ("", _) => key.clone(),
+                    (_, "value") => prefix.to_string(),
+                    _ => format!("{}_{}", prefix, key),
+                };
+                flatten(flat, &name, value);
             }
         }
-        s.end()
+        Value::Null | Value::Array(_) => {}
+        value => {
+            flat.insert(prefix.to_string(), value.clone());
+        }
     }
 }
 
+fn round(value: f64, digits: i32) -> f64 {
+    let k = 10f64.powi(digits);
+    (value * k).round() / k
+}
 
-fn string_to_static_str(s: String) -> &'static str {
-    Box::leak(s.into_boxed_str())
+// уже округлённое значение: без дробной части при precision = 0, 1013, а не 1013.0
+fn rounded(value: f64, precision: usize) -> Value {
+    match precision {
+        0 => Value::from(value as i64),
+        _ => Value::from(value),
+    }
 }
 
+const CHECKED_TEMPLATE: &str = "template";
+
+const UNIT_NAMES: [(Unit, &str); 3] = [(Celsius, "celsius"), (Kelvin, "kelvin"), (Fahrenheit, "fahrenheit")];
+
 pub struct Template<'a> {
     template: &'a str,
+    units: Units,
+    icons: Icons,
+    locale: Locale,
+    escape: Option<fn(&str) -> String>,
+    partials: Option<&'a BTreeMap<String, String>>,
 }
 
 impl<'a> Template<'a> {
     pub fn new(template: &'a str) -> Self {
         Template {
             template,
+            units: Units::default(),
+            icons: Icons::default(),
+            locale: Locale::default(),
+            escape: None,
+            partials: None,
         }
     }
 
-    pub fn render(&self, w: &WeatherInfo, debug: bool) -> Result<String, Error> {
-        let weather = WeatherInfoTemplate::from(w);
+    pub fn with_units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
 
-        let mut reg = Handlebars::new();
-        //reg.set_strict_mode(true);
-        reg.register_helper("created", Box::new(DateHelper));
+    pub fn with_icons(mut self, icons: Icons) -> Self {
+        self.icons = icons;
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    // экранирование значений {{ }} вместо html, например для polybar
+    pub fn with_escape(mut self, escape: fn(&str) -> String) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    // частичные шаблоны {{> name}}
+    pub fn with_partials(mut self, partials: &'a BTreeMap<String, String>) -> Self {
+        self.partials = Some(partials);
+        self
+    }
+
+    pub fn render(&self, w: &WeatherInfo, debug: bool) -> Result<String, Error> {
+        let weather = context(w, self.units, &self.icons, self.locale);
+        let reg = self.registry(false)?;
 
         let template = if debug { TEMPLATE_DEBUG } else { self.template };
 
@@ -281,10 +571,59 @@ impl<'a> Template<'a> {
 
         Ok(out)
     }
+
+    // значение поля контекста по плоскому имени: humidity, forecast_0_temperature, ...
+    pub fn field(&self, w: &WeatherInfo, name: &str) -> Option<Value> {
+        context(w, self.units, &self.icons, self.locale).get(name).cloned()
+    }
+
+    // Проверка шаблона в строгом режиме на полностью заполненных данных sample (см. sample::weather):
+    // неизвестные переменные и хелперы - ошибка с номером строки и колонки
+    pub fn check(&self, sample: &WeatherInfo) -> Result<(), Error> {
+        let mut reg = self.registry(true)?;
+        reg.register_template_string(CHECKED_TEMPLATE, self.template)
+            .map_err(|err| Error::InvalidTemplate(err.to_string()))?;
+        match reg.render(CHECKED_TEMPLATE, &context(sample, self.units, &self.icons, self.locale)) {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                let partial = match err.template_name.as_deref() {
+                    Some(name) if name != CHECKED_TEMPLATE => format!("partial {}, ", name),
+                    _ => String::new(),
+                };
+                let position = match (err.line_no, err.column_no) {
+                    (Some(line), Some(col)) => format!("line {}, col {}: ", line, col),
+                    _ => String::new(),
+                };
+                Err(Error::InvalidTemplate(format!("{}{}{}", partial, position, err.desc)))
+            }
+        }
+    }
+
+    fn registry(&self, strict: bool) -> Result<Handlebars<'a>, Error> {
+        let mut reg = Handlebars::new();
+        reg.set_strict_mode(strict);
+        if let Some(escape) = self.escape {
+            reg.register_escape_fn(escape);
+        }
+        reg.register_helper("created", Box::new(DateHelper { field: "date", locale: self.locale }));
+        reg.register_helper("helperMissing", Box::new(PrecisionHelper { units: self.units }));
+        reg.register_helper("sunrise", Box::new(DateHelper { field: "sunrise_date", locale: self.locale }));
+        reg.register_helper("sunset", Box::new(DateHelper { field: "sunset_date", locale: self.locale }));
+        helpers::register(&mut reg, self.units, &self.icons, self.locale);
+        for (name, partial) in self.partials.into_iter().flatten() {
+            reg.register_partial(name, partial)
+                .map_err(|err| Error::InvalidTemplate(format!("{}: {}", name, err)))?;
+        }
+        Ok(reg)
+    }
 }
 
+// выводит время из поля field, format - формат chrono, названия дней и месяцев на языке locale
 #[derive(Clone, Copy)]
-struct DateHelper;
+struct DateHelper {
+    field: &'static str,
+    locale: Locale,
+}
 
 impl HelperDef for DateHelper {
     fn call<'reg: 'rc, 'rc>(&self, h: &Helper<'reg, 'rc>, _: &'reg Handlebars<'reg>,
@@ -295,11 +634,200 @@ impl HelperDef for DateHelper {
             None => "%D %T".to_string(),
         };
         let obj = ctx.data().as_object().unwrap();
-        let date = obj.get("date").unwrap().clone();
-        let created_at: SystemTime = serde_json::from_value(date)?;
-        let datetime: DateTime<Local> = created_at.into();
-        let _ = out.write(&format!("{}", datetime.format(&fmt)));
+        let datetime = match obj.get(self.field).and_then(Value::as_i64).and_then(|t| Local.timestamp_opt(t, 0).single()) {
+            Some(datetime) => datetime,
+            None => return Ok(()),
+        };
+        let _ = out.write(&format!("{}", datetime.format(&self.locale.date_format(&fmt, &datetime))));
 
         Ok(())
     }
 }
+
+// Температура с заданной точностью и округлением:
+// {{ temperature_celsius precision=1 }}, {{ temperature_celsius_full precision=1 rounding="floor" }}.
+// Вызывается handlebars для любого выражения с параметрами, для которого нет хелпера.
+#[derive(Clone, Copy)]
+struct PrecisionHelper {
+    units: Units,
+}
+
+impl HelperDef for PrecisionHelper {
+    fn call<'reg: 'rc, 'rc>(&self, h: &Helper<'reg, 'rc>, r: &'reg Handlebars<'reg>,
+                            ctx: &'rc Context, _: &mut RenderContext<'reg, 'rc>,
+                            out: &mut dyn Output) -> HelperResult {
+        let name = h.name();
+        // отсутствующее поле без параметров выводится пустым
+        if h.params().is_empty() && h.hash().is_empty() && !r.strict_mode() {
+            return Ok(());
+        }
+        let (field, full) = match name.strip_suffix("_full") {
+            Some(field) => (field, true),
+            None => (name, false),
+        };
+        if !field.contains("temperature") {
+            return Err(RenderError::new(format!("Helper not defined: {}", name)));
+        }
+        // temperature, forecast_0_temperature - в единицах из units
+        let unit = UNIT_NAMES.iter()
+            .find(|(_, unit_name)| field.ends_with(&format!("_{}", unit_name)))
+            .map(|(unit, _)| *unit)
+            .unwrap_or(self.units.temperature);
+
+        let precision = match h.hash_get("precision").map(|v| v.value()) {
+            Some(v) => v.as_u64().ok_or_else(|| RenderError::new("precision must be a number"))? as u8,
+            None => 0,
+        };
+        let rounding: Rounding = match h.hash_get("rounding").map(|v| v.value()) {
+            Some(v) => v.as_str().unwrap_or_default().parse().map_err(RenderError::new)?,
+            None => Rounding::default(),
+        };
+
+        // поля нет, если провайдер не вернул значение
+        let raw = match ctx.data().get(format!("{}_raw", field)).and_then(|v| v.as_f64()) {
+            Some(raw) => raw,
+            None if r.strict_mode() => return Err(RenderError::strict_error(Some(&field.to_string()))),
+            None => return Ok(()),
+        };
+        let t = Temperature::new(raw, unit).round(precision, rounding);
+        let text = match full {
+            true => format!("{:.*}", precision as usize, t),
+            false => format!("{:.*}", precision as usize, t.val()),
+        };
+        out.write(&text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::time::Duration;
+
+    use crate::config::config::Provider;
+    use crate::output::polybar;
+    use crate::template::sample;
+    use crate::weather::weather::{Astro, Forecast, ForecastDay, WindDirection};
+
+    use super::*;
+
+    fn part(name: &str, temp: f64) -> ForecastPart {
+        ForecastPart {
+            name: name.to_string(),
+            temp: Temperature::new(temp, Celsius),
+            humidity: Some(80),
+            icon: None,
+            icon_url: None,
+            condition: Some(Condition::Cloudy),
+            feels_like: None,
+            daytime: Some(Daytime::Night),
+            wind: None,
+            pressure: Some(Pressure(1013.0)),
+            precipitation: None,
+        }
+    }
+
+    // фиксированные данные для тестов шаблонов и хелперов
+    pub(crate) fn weather() -> WeatherInfo {
+        let mut day = ForecastDay::new("2022-03-28");
+        day.temp_max = Some(Temperature::new(7, Celsius));
+        day.parts = vec![part("day", 6.0)];
+        day.hours = vec![part("12", 5.5)];
+        WeatherInfo {
+            temp: Some(Temperature::new(3.6, Celsius)),
+            humidity: Some(65),
+            condition: Some(Condition::Cloudy),
+            daytime: Some(Daytime::Day),
+            wind: Some(Wind { speed: 4.1, gust: None, dir: Some(WindDirection::S) }),
+            astro: Some(Astro {
+                sunrise: Some(UNIX_EPOCH + Duration::from_secs(1648431060)),
+                sunset: None,
+                moon_phase: None,
+            }),
+            forecasts: Some(Forecast {
+                parts: vec![part("evening", 1.5), part("night", -0.5)],
+                days: vec![day],
+            }),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn render(template: &str) -> String {
+        Template::new(template).render(&weather(), false).unwrap()
+    }
+
+    #[test]
+    fn flat_names() {
+        assert_eq!(render("{{ temperature_celsius_full }} {{ temperature }} {{ wind_speed_ms }} {{ wind_dir }}"),
+                   "4°C 4 4.1 S");
+        assert_eq!(render("{{ forecast_count }} {{ forecast_0_name }} {{ forecast_night_temperature_celsius_full }}"),
+                   "2 evening -1°C");
+        assert_eq!(render("{{ forecast_1_pressure_hpa }} {{ forecast_day_0_temperature_max_celsius }} \
+                           {{ forecast_day_0_day_temperature }} {{ forecast_day_0_hour_12_temperature_raw }}"),
+                   "1013 7 6 5.5");
+        assert_eq!(render("{{ forecast_0_pressure }} {{ forecast_0_pressure_mmhg }} {{ wind_speed_kmh }}"), "1013 760 15");
+        assert_eq!(render("{{ temperature_celsius precision=1 }} {{ cache }}"), "3.6 false");
+        assert!(!render("{{ sunrise }}").is_empty());
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(render("{{#each forecast.parts}}{{ name }}={{ temperature.full }} {{/each}}"),
+                   "evening=2°C night=-1°C ");
+        assert_eq!(render("{{#each forecast.daily}}{{ date }} {{ temperature_max.full }} {{ parts.0.name }}{{/each}}"),
+                   "2022-03-28 7°C day");
+        assert_eq!(render("{{ current.temperature.raw }} {{ current.wind.speed_full }} {{ current.pressure.full }}"),
+                   "3.6 4.1 m/s ");
+        assert_eq!(render("{{#if alerts}}alerts{{/if}}{{#each forecast.parts}}{{ pressure.full }} {{/each}}"),
+                   "1013 hPa 1013 hPa ");
+    }
+
+    #[test]
+    fn partials() {
+        let partials = BTreeMap::from([
+            ("part".to_string(), "{{ name }}:{{ temperature.value }}".to_string()),
+        ]);
+        let out = Template::new("{{ temperature }} {{#each forecast.parts}}{{> part}} {{/each}}")
+            .with_partials(&partials)
+            .render(&weather(), false)
+            .unwrap();
+        assert_eq!(out, "4 evening:2 night:-1 ");
+    }
+
+    #[test]
+    fn check() {
+        let sample = sample::weather(&[Provider::Yandex]);
+        let check = |template: &str| Template::new(template).check(&sample).map_err(|err| err.to_string());
+        assert!(check("{{ temperature_celsius precision=1 }} {{ forecast_day_6_hour_23_wind_dir }} \
+                       {{ providers.yandex.temperature }} {{ sunrise format=\"%H:%M\" }} {{ alert_0_title }} \
+                       {{#each forecast.daily}}{{ temp temperature_max }}{{ pad humidity 3 }}{{/each}}").is_ok());
+        assert_eq!(check("{{ temperature }}\n  {{ temprature_celsius }}").unwrap_err(),
+                   "Invalid template: line 2, col 3: Variable \"temprature_celsius\" not found in strict mode.");
+        assert!(check("{{ temperature_celsuis precision=1 }}").is_err());
+        assert!(check("{{ round current.tempreature.raw }}").is_err());
+        assert!(check("{{ providers.metno.temperature }}").is_err());
+        assert!(check("{{ formt temperature }}").unwrap_err().contains("Helper not defined: formt"));
+        assert!(check("{{#if temperature}}").unwrap_err().starts_with("Invalid template"));
+    }
+
+    #[test]
+    fn locale() {
+        let render = |template: &str| Template::new(template).with_locale(Locale::Ru).render(&weather(), false).unwrap();
+        assert_eq!(render("{{ condition }} {{ forecast_0_name }} {{ forecast_night_condition }}"),
+                   "облачно с прояснениями вечер облачно с прояснениями");
+        assert_eq!(render("{{#each forecast.daily}}{{ format_date date \"%a, %e %B\" }} {{ parts.0.name }}{{/each}}"),
+                   "пн, 28 марта день");
+        assert_eq!(render("{{ forecast_day_0_day_key }} {{ forecast_day_0_hour_12_name }}"), "day 12");
+    }
+
+    #[test]
+    fn escape() {
+        let w = WeatherInfo { provider: Some("100%{F-} & co".to_string()), ..weather() };
+        assert_eq!(Template::new("{{ provider }}").render(&w, false).unwrap(), "100%{F-} &amp; co");
+        assert_eq!(Template::new("{{ provider }}%").with_escape(polybar::escape).render(&w, false).unwrap(),
+                   "100%%{F-} & co%");
+        // хелперы тоже экранируют текст провайдера
+        assert_eq!(Template::new("{{ truncate provider 20 }} {{ pad provider 15 char=\"%\" }}")
+                       .with_escape(polybar::escape).render(&w, false).unwrap(),
+                   "100%%{F-} & co %%%%100%%{F-} & co");
+    }
+}
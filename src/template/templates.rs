@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use handlebars::template::{Parameter, Template, TemplateElement};
+
+use crate::config::config::Config;
+use crate::Error;
+
+const TEMPLATE_EXTENSION: &str = "hbs";
+
+// имя шаблона display в views и в отчёте check
+const DISPLAY: &str = "display";
+
+// Шаблоны из настроек: display или display_file, именованные из [templates] и файлы *.hbs
+// каталога templates_dir. Именованные шаблоны доступны и как частичные: {{> tooltip}}.
+pub struct Templates {
+    display: String,
+    named: BTreeMap<String, String>,
+}
+
+impl Templates {
+    pub fn from_config(c: &Config) -> Result<Self, Error> {
+        let display = match &c.display_file {
+            Some(path) => read(Path::new(path))?,
+            None => c.display.clone(),
+        };
+        let mut templates = Templates {
+            display,
+            named: BTreeMap::new(),
+        };
+        if let Some(dir) = &c.templates_dir {
+            templates.load_dir(Path::new(dir))?;
+        }
+        // шаблоны из config.toml важнее файлов
+        templates.named.extend(c.templates.clone());
+        Ok(templates)
+    }
+
+    // name - из --template или views, без него и для "display" - display
+    pub fn get(&self, name: Option<&str>) -> Result<&str, Error> {
+        let name = match name {
+            Some(DISPLAY) | None => return Ok(&self.display),
+            Some(name) => name,
+        };
+        match self.named.get(name) {
+            Some(template) => Ok(template),
+            None => {
+                let names: Vec<&str> = self.named.keys().map(String::as_str).collect();
+                Err(Error::UnknownTemplate(format!("{} (available: {})", name, names.join(", "))))
+            }
+        }
+    }
+
+    pub fn partials(&self) -> &BTreeMap<String, String> {
+        &self.named
+    }
+
+    // Шаблоны для check: display и именованные. Подключаемый в другие шаблоны {{> name}}
+    // проверяется в их составе, отдельно у него может не быть контекста (например, внутри #each).
+    pub fn checked(&self) -> Vec<(&str, &str)> {
+        let mut included = HashSet::new();
+        for source in std::iter::once(&self.display).chain(self.named.values()) {
+            if let Ok(template) = Template::compile(source) {
+                partial_names(&template, &mut included);
+            }
+        }
+        std::iter::once((DISPLAY, self.display.as_str()))
+            .chain(self.named.iter()
+                .filter(|(name, _)| !included.contains(name.as_str()))
+                .map(|(name, template)| (name.as_str(), template.as_str())))
+            .collect()
+    }
+
+    // каталога может не быть
+    fn load_dir(&mut self, dir: &Path) -> Result<(), Error> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(()),
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(TEMPLATE_EXTENSION) {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                self.named.insert(name.to_string(), read(&path)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn partial_names(template: &Template, names: &mut HashSet<String>) {
+    for element in &template.elements {
+        match element {
+            TemplateElement::PartialExpression(partial) | TemplateElement::PartialBlock(partial) => {
+                if let Parameter::Name(name) = &partial.name {
+                    names.insert(name.clone());
+                }
+                partial.template.iter().for_each(|t| partial_names(t, names));
+            }
+            TemplateElement::HelperBlock(helper) => {
+                helper.template.iter().chain(helper.inverse.iter()).for_each(|t| partial_names(t, names));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| Error::FailedReadTemplate(format!("{}: {}", path.display(), err)))?;
+    // перевод строки в конце файла не выводится
+    Ok(content.strip_suffix('\n').map(str::to_string).unwrap_or(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_dir() {
+        let dir = std::env::temp_dir().join(format!("forecast-templates-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("tooltip.hbs"), "{{ temperature_full }}\n").unwrap();
+        fs::write(dir.join("notes.txt"), "skipped").unwrap();
+
+        let mut templates = Templates {
+            display: "{{> tooltip}}".to_string(),
+            named: BTreeMap::new(),
+        };
+        templates.load_dir(&dir).unwrap();
+        templates.load_dir(&dir.join("missing")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(templates.get(None).unwrap(), "{{> tooltip}}");
+        assert_eq!(templates.get(Some("display")).unwrap(), "{{> tooltip}}");
+        assert_eq!(templates.get(Some("tooltip")).unwrap(), "{{ temperature_full }}");
+        assert_eq!(templates.partials().len(), 1);
+        let err = templates.get(Some("bar")).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown template: bar (available: tooltip)");
+    }
+
+    #[test]
+    fn checked_skips_partials() {
+        let templates = Templates {
+            display: "{{#each forecast.parts}}{{> part}}{{/each}}".to_string(),
+            named: BTreeMap::from([
+                ("part".to_string(), "{{ name }}".to_string()),
+                ("tooltip".to_string(), "{{ temperature }}".to_string()),
+            ]),
+        };
+        let names: Vec<&str> = templates.checked().iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["display", "tooltip"]);
+    }
+}
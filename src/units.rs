@@ -0,0 +1,264 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{de, Deserialize, Deserializer};
+use serde::de::{IntoDeserializer, MapAccess, Visitor};
+
+use crate::temperature::Unit;
+use crate::weather::weather::Pressure;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedUnit {
+    // м/с
+    Ms,
+    Kmh,
+    Mph,
+    Knots,
+}
+
+impl SpeedUnit {
+    pub fn convert(&self, ms: f64) -> f64 {
+        match self {
+            SpeedUnit::Ms => ms,
+            SpeedUnit::Kmh => ms * 3.6,
+            SpeedUnit::Mph => ms * 3600.0 / 1609.344,
+            SpeedUnit::Knots => ms * 3600.0 / 1852.0,
+        }
+    }
+}
+
+impl Display for SpeedUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            SpeedUnit::Ms => "m/s",
+            SpeedUnit::Kmh => "km/h",
+            SpeedUnit::Mph => "mph",
+            SpeedUnit::Knots => "kn",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureUnit {
+    Hpa,
+    Pa,
+    Mmhg,
+    Inhg,
+}
+
+impl PressureUnit {
+    pub fn convert(&self, pressure: Pressure) -> f64 {
+        match self {
+            PressureUnit::Hpa => pressure.hpa(),
+            PressureUnit::Pa => pressure.hpa() * 100.0,
+            PressureUnit::Mmhg => pressure.mm_hg(),
+            PressureUnit::Inhg => pressure.in_hg(),
+        }
+    }
+
+    // знаков после запятой при выводе
+    pub fn precision(&self) -> usize {
+        match self {
+            PressureUnit::Inhg => 2,
+            _ => 0,
+        }
+    }
+}
+
+impl Display for PressureUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            PressureUnit::Hpa => "hPa",
+            PressureUnit::Pa => "Pa",
+            PressureUnit::Mmhg => "mmHg",
+            PressureUnit::Inhg => "inHg",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    Mm,
+    In,
+}
+
+impl LengthUnit {
+    pub fn convert(&self, mm: f64) -> f64 {
+        match self {
+            LengthUnit::Mm => mm,
+            LengthUnit::In => mm / 25.4,
+        }
+    }
+
+    pub fn precision(&self) -> usize {
+        match self {
+            LengthUnit::Mm => 1,
+            LengthUnit::In => 2,
+        }
+    }
+}
+
+impl Display for LengthUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            LengthUnit::Mm => "mm",
+            LengthUnit::In => "in",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+// Единицы вывода: units = "metric" | "imperial" | "si", либо таблица [units]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "UnitsConfig")]
+pub struct Units {
+    pub temperature: Unit,
+    pub wind: SpeedUnit,
+    pub pressure: PressureUnit,
+    pub precipitation: LengthUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    Metric,
+    Imperial,
+    Si,
+}
+
+impl Units {
+    pub fn system(system: UnitSystem) -> Self {
+        match system {
+            UnitSystem::Metric => Units {
+                temperature: Unit::Celsius,
+                wind: SpeedUnit::Ms,
+                pressure: PressureUnit::Hpa,
+                precipitation: LengthUnit::Mm,
+            },
+            UnitSystem::Imperial => Units {
+                temperature: Unit::Fahrenheit,
+                wind: SpeedUnit::Mph,
+                pressure: PressureUnit::Inhg,
+                precipitation: LengthUnit::In,
+            },
+            UnitSystem::Si => Units {
+                temperature: Unit::Kelvin,
+                wind: SpeedUnit::Ms,
+                pressure: PressureUnit::Pa,
+                precipitation: LengthUnit::Mm,
+            },
+        }
+    }
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units::system(UnitSystem::Metric)
+    }
+}
+
+// отсутствующие в таблице единицы берутся из metric
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomUnits {
+    temperature: Option<Unit>,
+    wind: Option<SpeedUnit>,
+    pressure: Option<PressureUnit>,
+    precipitation: Option<LengthUnit>,
+}
+
+enum UnitsConfig {
+    System(UnitSystem),
+    Custom(CustomUnits),
+}
+
+// строка - система единиц, таблица - свои единицы; ошибка называет неверный ключ или значение
+impl<'de> Deserialize<'de> for UnitsConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UnitsVisitor;
+
+        impl<'de> Visitor<'de> for UnitsVisitor {
+            type Value = UnitsConfig;
+
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                write!(f, "\"metric\", \"imperial\", \"si\" or a [units] table")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                UnitSystem::deserialize(s.into_deserializer()).map(UnitsConfig::System)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                CustomUnits::deserialize(de::value::MapAccessDeserializer::new(map)).map(UnitsConfig::Custom)
+            }
+        }
+
+        deserializer.deserialize_any(UnitsVisitor)
+    }
+}
+
+impl From<UnitsConfig> for Units {
+    fn from(config: UnitsConfig) -> Self {
+        match config {
+            UnitsConfig::System(system) => Units::system(system),
+            UnitsConfig::Custom(custom) => {
+                let metric = Units::default();
+                Units {
+                    temperature: custom.temperature.unwrap_or(metric.temperature),
+                    wind: custom.wind.unwrap_or(metric.wind),
+                    pressure: custom.pressure.unwrap_or(metric.pressure),
+                    precipitation: custom.precipitation.unwrap_or(metric.precipitation),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Config {
+        units: Units,
+    }
+
+    #[test]
+    fn parse_units() {
+        let c: Config = toml::from_str(r#"units = "imperial""#).unwrap();
+        assert_eq!(c.units, Units::system(UnitSystem::Imperial));
+
+        let c: Config = toml::from_str(r#"
+            [units]
+            temperature = "fahrenheit"
+            wind = "kmh"
+            pressure = "mmhg"
+        "#).unwrap();
+        assert_eq!(c.units.temperature, Unit::Fahrenheit);
+        assert_eq!(c.units.wind, SpeedUnit::Kmh);
+        assert_eq!(c.units.pressure, PressureUnit::Mmhg);
+        assert_eq!(c.units.precipitation, LengthUnit::Mm);
+
+        let c: Config = toml::from_str("[units]\ntemperature = \"Kelvin\"").unwrap();
+        assert_eq!(c.units.temperature, Unit::Kelvin);
+
+        let err = |s| toml::from_str::<Config>(s).err().unwrap().to_string();
+        assert!(err(r#"units = "furlongs""#).contains("unknown variant `furlongs`"));
+        assert!(err("[units]\ntemprature = \"F\"").contains("unknown field `temprature`"));
+        assert!(err("[units]\nwind = \"kph\"").contains("unknown variant `kph`"));
+    }
+
+    #[test]
+    fn convert() {
+        assert_eq!(SpeedUnit::Kmh.convert(10.0), 36.0);
+        assert_eq!((SpeedUnit::Mph.convert(10.0) * 100.0).round(), 2237.0);
+        assert_eq!(PressureUnit::Pa.convert(Pressure(1013.0)), 101300.0);
+        assert_eq!(LengthUnit::In.convert(25.4), 1.0);
+    }
+}
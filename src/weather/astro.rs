@@ -0,0 +1,135 @@
+use std::f64::consts::PI;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::weather::weather::{Astro, Daytime, MoonPhase, WeatherInfo};
+
+// юлианская дата 1970-01-01T00:00Z и эпохи J2000
+const JULIAN_UNIX_EPOCH: f64 = 2440587.5;
+const J2000: f64 = 2451545.0;
+// синодический месяц, дни
+const SYNODIC_MONTH: f64 = 29.530588853;
+// новолуние 2000-01-06 18:14 UTC
+const NEW_MOON_J2000: f64 = 2451550.26;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sun {
+    // восход и заход, unix-время
+    Rises { sunrise: i64, sunset: i64 },
+    PolarDay,
+    PolarNight,
+}
+
+impl Sun {
+    pub fn daytime(&self, unix: i64) -> Daytime {
+        match *self {
+            Sun::Rises { sunrise, sunset } if sunrise <= unix && unix < sunset => Daytime::Day,
+            Sun::Rises { .. } => Daytime::Night,
+            Sun::PolarDay => Daytime::Day,
+            Sun::PolarNight => Daytime::Night,
+        }
+    }
+}
+
+// Восход и заход солнца в сутки, содержащие unix-время (по местному солнечному времени).
+// https://en.wikipedia.org/wiki/Sunrise_equation
+pub fn sun(lat: f64, lon: f64, unix: i64) -> Sun {
+    // номер суток по местному солнечному времени
+    let day = (unix as f64 / 86400.0 + lon / 360.0).floor();
+    // средний солнечный полдень в днях от J2000
+    let noon = day + 0.5 + JULIAN_UNIX_EPOCH - J2000 - lon / 360.0;
+
+    let m = (357.5291 + 0.98560028 * noon).rem_euclid(360.0).to_radians();
+    let c = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let lambda = (m.to_degrees() + c + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = noon + 0.0053 * m.sin() - 0.0069 * (2.0 * lambda).sin();
+
+    let declination = (lambda.sin() * 23.4397f64.to_radians().sin()).asin();
+    let phi = lat.to_radians();
+    let cos_omega = ((-0.833f64).to_radians().sin() - phi.sin() * declination.sin())
+        / (phi.cos() * declination.cos());
+    if cos_omega > 1.0 {
+        return Sun::PolarNight;
+    }
+    if cos_omega < -1.0 {
+        return Sun::PolarDay;
+    }
+    let omega = cos_omega.acos() / (2.0 * PI);
+    let to_unix = |j: f64| ((j + J2000 - JULIAN_UNIX_EPOCH) * 86400.0).round() as i64;
+    Sun::Rises {
+        sunrise: to_unix(transit - omega),
+        sunset: to_unix(transit + omega),
+    }
+}
+
+pub fn moon_phase(unix: i64) -> MoonPhase {
+    let julian = unix as f64 / 86400.0 + JULIAN_UNIX_EPOCH;
+    let age = ((julian - NEW_MOON_J2000) / SYNODIC_MONTH).rem_euclid(1.0);
+    MoonPhase::from_index((age * 8.0).round() as usize % 8)
+}
+
+// Дополняет данные провайдера вычисленными: восход, заход, фаза луны и время суток
+pub fn complete(weather: &mut WeatherInfo, lat: &str, lon: &str) {
+    if weather.temp.is_none() {
+        return;
+    }
+    let (lat, lon) = match (lat.trim().parse::<f64>(), lon.trim().parse::<f64>()) {
+        (Ok(lat), Ok(lon)) => (lat, lon),
+        _ => return,
+    };
+    let now = unix(SystemTime::now());
+    let sun = sun(lat, lon, now);
+
+    let astro = weather.astro.get_or_insert(Astro {
+        sunrise: None,
+        sunset: None,
+        moon_phase: None,
+    });
+    if let Sun::Rises { sunrise, sunset } = sun {
+        astro.sunrise = astro.sunrise.or_else(|| Some(system_time(sunrise)));
+        astro.sunset = astro.sunset.or_else(|| Some(system_time(sunset)));
+    }
+    astro.moon_phase = astro.moon_phase.or_else(|| Some(moon_phase(now)));
+    weather.daytime = weather.daytime.or_else(|| Some(sun.daytime(now)));
+}
+
+fn unix(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn system_time(unix: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(unix.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sunrise_sunset() {
+        // Лондон, 2022-06-21: восход 03:43, заход 20:21 UTC
+        let sun = sun(51.5074, -0.1278, 1655812800);
+        match sun {
+            Sun::Rises { sunrise, sunset } => {
+                assert!((sunrise - 1655782980).abs() < 180, "sunrise {}", sunrise);
+                assert!((sunset - 1655842860).abs() < 180, "sunset {}", sunset);
+                assert_eq!(sun.daytime(1655812800), Daytime::Day);
+                assert_eq!(sun.daytime(1655780000), Daytime::Night);
+            }
+            _ => panic!("expected sunrise, got {:?}", sun),
+        }
+    }
+
+    #[test]
+    fn polar() {
+        // Мурманск: полярная ночь в декабре, полярный день в июне
+        assert_eq!(sun(68.97, 33.07, 1671537600), Sun::PolarNight);
+        assert_eq!(sun(68.97, 33.07, 1655726400), Sun::PolarDay);
+    }
+
+    #[test]
+    fn moon() {
+        // полнолуние 2022-03-18, новолуние 2022-04-01
+        assert_eq!(moon_phase(1647600000), MoonPhase::FullMoon);
+        assert_eq!(moon_phase(1648800000), MoonPhase::NewMoon);
+    }
+}
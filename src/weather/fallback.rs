@@ -0,0 +1,89 @@
+use crate::Error;
+use crate::weather::provider::{WeatherGetter, WeatherQueryType};
+use crate::weather::weather::WeatherInfo;
+
+// Опрашивает провайдеров по порядку и возвращает первый успешный ответ.
+// Если ответить не смог никто, возвращается ошибка последнего провайдера.
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn WeatherGetter>>,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<Box<dyn WeatherGetter>>) -> Self {
+        FallbackProvider { providers }
+    }
+}
+
+impl WeatherGetter for FallbackProvider {
+    fn get(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        let mut last_error = Error::InvalidConfigCheck("expected provider or providers".to_string());
+        for provider in self.providers.iter() {
+            match provider.get(query.clone()) {
+                Ok(weather) => return Ok(weather),
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::temperature::Temperature;
+    use crate::temperature::Unit::Celsius;
+
+    use super::*;
+
+    struct Fake {
+        name: &'static str,
+        code: Option<u16>,
+        calls: Rc<Cell<u32>>,
+    }
+
+    impl WeatherGetter for Fake {
+        fn get(&self, _: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+            self.calls.set(self.calls.get() + 1);
+            if let Some(code) = self.code {
+                return Err(Error::InvalidRequest { text: self.name.to_string(), code });
+            }
+            Ok(WeatherInfo {
+                provider: Some(self.name.to_string()),
+                temp: Some(Temperature::new(1, Celsius)),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn fake(name: &'static str, code: Option<u16>, calls: &Rc<Cell<u32>>) -> Box<dyn WeatherGetter> {
+        Box::new(Fake { name, code, calls: calls.clone() })
+    }
+
+    #[test]
+    fn falls_back_on_error() {
+        let calls = Rc::new(Cell::new(0));
+        let provider = FallbackProvider::new(vec![
+            fake("yandex", Some(429), &calls),
+            fake("openmeteo", None, &calls),
+            fake("metno", None, &calls),
+        ]);
+
+        let w = provider.get(vec![WeatherQueryType::Current]).unwrap();
+        assert_eq!(w.provider.as_deref(), Some("openmeteo"));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn last_error_when_all_failed() {
+        let calls = Rc::new(Cell::new(0));
+        let provider = FallbackProvider::new(vec![
+            fake("yandex", Some(429), &calls),
+            fake("openmeteo", Some(500), &calls),
+        ]);
+
+        let err = provider.get(vec![WeatherQueryType::Current]).unwrap_err();
+        assert!(matches!(err, Error::InvalidRequest { code: 500, .. }));
+    }
+}
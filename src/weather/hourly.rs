@@ -0,0 +1,136 @@
+use chrono::{TimeZone, Utc};
+
+use crate::temperature::Temperature;
+use crate::temperature::Unit::Celsius;
+use crate::weather::provider::WeatherQueryType;
+use crate::weather::weather::{Forecast, ForecastDay, ForecastPart, part_of_day, PART_NAMES, Precipitation, Pressure, Wind};
+
+// столько же частей прогноза, сколько отдаёт yandex informers
+const FORECAST_PARTS: usize = 2;
+
+// Интервал почасового (или трёхчасового) прогноза провайдера
+pub struct Hour {
+    // локальное unix-время начала интервала
+    pub local: i64,
+    pub part: ForecastPart,
+}
+
+// Прогноз из почасовых интервалов для провайдеров без готовых частей суток.
+// Daily: ближайшие части суток после now и сводка по дням, Hourly: часы по дням.
+pub fn forecast(hours: &[Hour], now: i64, query: &[WeatherQueryType]) -> Option<Forecast> {
+    let daily = query.contains(&WeatherQueryType::Daily);
+    let hourly = query.contains(&WeatherQueryType::Hourly);
+    if !daily && !hourly {
+        return None;
+    }
+
+    let mut forecast = Forecast {
+        parts: Vec::new(),
+        days: Vec::new(),
+    };
+    if daily {
+        let now = part_of_day(now);
+        let upcoming: Vec<&Hour> = hours.iter().filter(|h| part_of_day(h.local) > now).collect();
+        forecast.parts = group(&upcoming, |h| part_of_day(h.local))
+            .into_iter()
+            .take(FORECAST_PARTS)
+            .map(|((_, part), hours)| aggregate(PART_NAMES[part], &hours))
+            .collect();
+    }
+
+    let all: Vec<&Hour> = hours.iter().collect();
+    for (_, hours) in group(&all, |h| part_of_day(h.local).0) {
+        let mut day = ForecastDay::new(&date(hours[0].local));
+        if daily {
+            let summary = aggregate("", &hours);
+            day.temp_min = hours.iter().map(|h| h.part.temp).min();
+            day.temp_max = hours.iter().map(|h| h.part.temp).max();
+            day.humidity = summary.humidity;
+            day.icon = summary.icon;
+            day.icon_url = summary.icon_url;
+            day.condition = summary.condition;
+            day.parts = group(&hours, |h| part_of_day(h.local).1)
+                .into_iter()
+                .map(|(part, hours)| aggregate(PART_NAMES[part], &hours))
+                .collect();
+        }
+        if hourly {
+            day.hours = hours.iter()
+                .map(|h| ForecastPart {
+                    name: (h.local.rem_euclid(86400) / 3600).to_string(),
+                    ..h.part.clone()
+                })
+                .collect();
+        }
+        forecast.days.push(day);
+    }
+    Some(forecast)
+}
+
+// группировка подряд идущих интервалов с одинаковым ключом
+fn group<'a, K: PartialEq>(hours: &[&'a Hour], key: impl Fn(&Hour) -> K) -> Vec<(K, Vec<&'a Hour>)> {
+    let mut groups: Vec<(K, Vec<&Hour>)> = Vec::new();
+    for h in hours {
+        let k = key(h);
+        match groups.last_mut() {
+            Some((last, items)) if *last == k => items.push(h),
+            _ => groups.push((k, vec![h])),
+        }
+    }
+    groups
+}
+
+// средние значения по интервалам, состояние погоды - из середины
+fn aggregate(name: &str, hours: &[&Hour]) -> ForecastPart {
+    let middle = &hours[hours.len() / 2].part;
+    ForecastPart {
+        name: name.to_string(),
+        temp: average(hours.iter().map(|h| Some(h.part.temp)))
+            .unwrap_or(middle.temp),
+        feels_like: average(hours.iter().map(|h| h.part.feels_like)),
+        humidity: mean(hours.iter().filter_map(|h| h.part.humidity).map(|v| v as f64))
+            .map(|h| h.round() as u64),
+        wind: mean(hours.iter().filter_map(|h| h.part.wind).map(|w| w.speed))
+            .map(|speed| Wind {
+                speed,
+                gust: hours.iter().filter_map(|h| h.part.wind?.gust).reduce(f64::max),
+                dir: middle.wind.and_then(|w| w.dir),
+            }),
+        pressure: mean(hours.iter().filter_map(|h| h.part.pressure).map(|p| p.hpa()))
+            .map(Pressure),
+        precipitation: precipitation(hours),
+        ..middle.clone()
+    }
+}
+
+// суммарное количество и максимальная вероятность осадков
+fn precipitation(hours: &[&Hour]) -> Option<Precipitation> {
+    let values: Vec<Precipitation> = hours.iter().filter_map(|h| h.part.precipitation).collect();
+    if values.is_empty() {
+        return None;
+    }
+    let mm: Vec<f64> = values.iter().filter_map(|p| p.mm).collect();
+    Some(Precipitation {
+        mm: (!mm.is_empty()).then(|| mm.iter().sum()),
+        prob: values.iter().filter_map(|p| p.prob).max(),
+    })
+}
+
+fn average(temps: impl Iterator<Item=Option<Temperature>>) -> Option<Temperature> {
+    let avg = mean(temps.flatten().map(|t| t.as_unit(Celsius).val()))?;
+    Some(Temperature::new(avg, Celsius))
+}
+
+fn mean(values: impl Iterator<Item=f64>) -> Option<f64> {
+    let values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn date(local: i64) -> String {
+    Utc.timestamp_opt(local, 0).single()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::weather::weather::{Condition, Daytime};
+
+// Набор иконок погоды
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconSet {
+    // шрифт Weather Icons (font-3 = weathericons в polybar)
+    #[default]
+    WeatherIcons,
+    // Nerd Font, иконки nf-weather-*
+    NerdFont,
+    // эмодзи Unicode
+    Emoji,
+    // обозначения METAR: SKC, OVC, -RA, TSRA, ...
+    Ascii,
+}
+
+impl IconSet {
+    pub fn icon(&self, condition: Condition, daytime: Daytime) -> String {
+        match self {
+            IconSet::WeatherIcons => condition.icon(daytime).to_string(),
+            IconSet::NerdFont => nerd_font(condition, daytime).to_string(),
+            IconSet::Emoji => emoji(condition, daytime).to_string(),
+            IconSet::Ascii => ascii(condition).to_string(),
+        }
+    }
+}
+
+impl FromStr for IconSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "weathericons" => Ok(IconSet::WeatherIcons),
+            "nerdfont" => Ok(IconSet::NerdFont),
+            "emoji" => Ok(IconSet::Emoji),
+            "ascii" => Ok(IconSet::Ascii),
+            _ => Err(format!("unknown icon set {}", s)),
+        }
+    }
+}
+
+// Иконки из config.toml: набор и свои иконки для отдельных состояний погоды
+// [icons]
+// set = "emoji"
+// Overcast = "☁"
+// Clear = { day = "☀", night = "🌙" }
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Icons {
+    #[serde(default)]
+    pub set: IconSet,
+
+    #[serde(flatten)]
+    pub custom: HashMap<Condition, CustomIcon>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum CustomIcon {
+    Any(String),
+    // без night (day) - иконка набора
+    Daytime {
+        day: Option<String>,
+        night: Option<String>,
+    },
+}
+
+impl Icons {
+    pub fn icon(&self, condition: Condition, daytime: Daytime) -> String {
+        let custom = match self.custom.get(&condition) {
+            Some(CustomIcon::Any(icon)) => Some(icon),
+            Some(CustomIcon::Daytime { day, night }) => match daytime {
+                Daytime::Day => day.as_ref(),
+                Daytime::Night => night.as_ref(),
+            },
+            None => None,
+        };
+        match custom {
+            Some(icon) => icon.clone(),
+            None => self.set.icon(condition, daytime),
+        }
+    }
+}
+
+fn nerd_font(condition: Condition, daytime: Daytime) -> char {
+    let night = daytime == Daytime::Night;
+    match condition {
+        // nf-weather-day_sunny, nf-weather-night_clear
+        Condition::Clear => if night { '\u{e32b}' } else { '\u{e30d}' },
+        // nf-weather-day_cloudy, nf-weather-night_alt_cloudy
+        Condition::PartlyCloudy => if night { '\u{e37e}' } else { '\u{e302}' },
+        // nf-weather-cloud
+        Condition::Cloudy => '\u{e33d}',
+        // nf-weather-cloudy
+        Condition::Overcast => '\u{e312}',
+        // nf-weather-day_sprinkle, nf-weather-sprinkle
+        Condition::Drizzle => if night { '\u{e31b}' } else { '\u{e30b}' },
+        // nf-weather-day_showers, nf-weather-night_alt_showers
+        Condition::LightRain | Condition::Showers => if night { '\u{e326}' } else { '\u{e309}' },
+        // nf-weather-day_rain, nf-weather-night_alt_rain
+        Condition::Rain => if night { '\u{e325}' } else { '\u{e308}' },
+        // nf-weather-rain
+        Condition::ModerateRain | Condition::HeavyRain | Condition::ContinuousHeavyRain => '\u{e318}',
+        // nf-weather-rain_mix
+        Condition::WetSnow => '\u{e316}',
+        // nf-weather-day_snow, nf-weather-night_alt_snow
+        Condition::LightSnow | Condition::Snow => if night { '\u{e327}' } else { '\u{e30a}' },
+        // nf-weather-snow
+        Condition::SnowShowers => '\u{e31a}',
+        // nf-weather-hail
+        Condition::Hail => '\u{e314}',
+        // nf-weather-day_lightning, nf-weather-night_alt_lightning
+        Condition::Thunderstorm => if night { '\u{e322}' } else { '\u{e305}' },
+        // nf-weather-thunderstorm
+        Condition::ThunderstormWithRain | Condition::ThunderstormWithHail => '\u{e31d}',
+    }
+}
+
+fn emoji(condition: Condition, daytime: Daytime) -> &'static str {
+    let night = daytime == Daytime::Night;
+    match condition {
+        Condition::Clear => if night { "🌙" } else { "☀" },
+        Condition::PartlyCloudy => if night { "☁" } else { "🌤" },
+        Condition::Cloudy => if night { "☁" } else { "🌥" },
+        Condition::Overcast => "☁",
+        Condition::Drizzle | Condition::LightRain => if night { "🌧" } else { "🌦" },
+        Condition::Rain | Condition::ModerateRain | Condition::HeavyRain |
+        Condition::ContinuousHeavyRain | Condition::Showers => "🌧",
+        Condition::WetSnow | Condition::LightSnow | Condition::Hail => "🌨",
+        Condition::Snow | Condition::SnowShowers => "❄",
+        Condition::Thunderstorm => "🌩",
+        Condition::ThunderstormWithRain | Condition::ThunderstormWithHail => "⛈",
+    }
+}
+
+fn ascii(condition: Condition) -> &'static str {
+    match condition {
+        Condition::Clear => "SKC",
+        Condition::PartlyCloudy => "FEW",
+        Condition::Cloudy => "BKN",
+        Condition::Overcast => "OVC",
+        Condition::Drizzle => "DZ",
+        Condition::LightRain => "-RA",
+        Condition::Rain | Condition::ModerateRain => "RA",
+        Condition::HeavyRain | Condition::ContinuousHeavyRain => "+RA",
+        Condition::Showers => "SHRA",
+        Condition::WetSnow => "RASN",
+        Condition::LightSnow => "-SN",
+        Condition::Snow => "SN",
+        Condition::SnowShowers => "SHSN",
+        Condition::Hail => "GR",
+        Condition::Thunderstorm => "TS",
+        Condition::ThunderstormWithRain => "TSRA",
+        Condition::ThunderstormWithHail => "TSGR",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Config {
+        icons: Icons,
+    }
+
+    #[test]
+    fn custom_icons() {
+        let c: Config = toml::from_str(r#"
+            [icons]
+            set = "ascii"
+            Overcast = "cloud"
+            Clear = { day = "sun", night = "moon" }
+            Snow = { night = "snow" }
+        "#).unwrap();
+        let icons = c.icons;
+        assert_eq!(icons.set, IconSet::Ascii);
+        assert_eq!(icons.icon(Condition::Overcast, Daytime::Night), "cloud");
+        assert_eq!(icons.icon(Condition::Clear, Daytime::Day), "sun");
+        assert_eq!(icons.icon(Condition::Clear, Daytime::Night), "moon");
+        assert_eq!(icons.icon(Condition::Snow, Daytime::Day), "SN");
+        assert_eq!(icons.icon(Condition::Rain, Daytime::Day), "RA");
+
+        assert!(toml::from_str::<Config>("[icons]\nSunny = \"sun\"").is_err());
+        assert!(toml::from_str::<Config>("[icons]\nset = \"webdings\"").is_err());
+    }
+
+    #[test]
+    fn default_set() {
+        let icons = Icons::default();
+        assert_eq!(icons.icon(Condition::Clear, Daytime::Night), Condition::Clear.icon(Daytime::Night).to_string());
+        assert_eq!(IconSet::Emoji.icon(Condition::Clear, Daytime::Night), "🌙");
+        assert_eq!("NerdFont".parse::<IconSet>(), Ok(IconSet::NerdFont));
+    }
+}
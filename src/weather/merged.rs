@@ -0,0 +1,159 @@
+use std::time::SystemTime;
+
+use crate::Error;
+use crate::temperature::Temperature;
+use crate::temperature::Unit::Celsius;
+use crate::weather::provider::{WeatherGetter, WeatherQueryType};
+use crate::weather::weather::{Condition, WeatherInfo};
+
+// Опрашивает всех провайдеров и объединяет ответы: средняя температура,
+// состояние погоды большинством голосов, максимальная влажность.
+// Ответы отдельных провайдеров сохраняются в WeatherInfo::sources.
+pub struct MergedProvider {
+    providers: Vec<Box<dyn WeatherGetter>>,
+}
+
+impl MergedProvider {
+    pub fn new(providers: Vec<Box<dyn WeatherGetter>>) -> Self {
+        MergedProvider { providers }
+    }
+}
+
+impl WeatherGetter for MergedProvider {
+    fn get(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        let mut sources = Vec::new();
+        let mut last_error = None;
+        for provider in self.providers.iter() {
+            match provider.get(query.clone()) {
+                Ok(weather) => sources.push(weather),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        if sources.is_empty() {
+            return Err(last_error.unwrap_or_else(||
+                Error::InvalidConfigCheck("expected provider or providers".to_string())));
+        }
+        Ok(merge(sources))
+    }
+}
+
+fn merge(sources: Vec<WeatherInfo>) -> WeatherInfo {
+    let temp = average(sources.iter().map(|w| w.temp));
+    let feels_like = average(sources.iter().map(|w| w.feels_like));
+    let humidity = sources.iter().filter_map(|w| w.humidity).max();
+    let condition = majority(sources.iter().filter_map(|w| w.condition));
+    let expires_at = sources.iter().filter_map(|w| w.expires_at).min();
+
+    let names: Vec<&str> = sources.iter().filter_map(|w| w.provider.as_deref()).collect();
+    let provider = Some(names.join("+"));
+
+    // иконка и прогноз берутся у первого провайдера, согласного с итоговым состоянием
+    let first = sources.iter()
+        .position(|w| w.condition == condition)
+        .unwrap_or(0);
+    let icon = sources[first].icon.clone();
+    let icon_url = sources[first].icon_url.clone();
+    let daytime = sources[first].daytime;
+    // копия: providers.<имя> сохраняют свой прогноз
+    let forecasts = sources.iter()
+        .find_map(|w| w.forecasts.clone());
+    let alerts = sources.iter()
+        .find_map(|w| w.alerts.clone());
+    let wind = sources.iter().find_map(|w| w.wind);
+    let pressure = sources.iter().find_map(|w| w.pressure);
+    let precipitation = sources.iter().find_map(|w| w.precipitation);
+    let astro = sources.iter().find_map(|w| w.astro);
+
+    WeatherInfo {
+        is_cached: false,
+        provider,
+        created_at: SystemTime::now(),
+        temp,
+        feels_like,
+        humidity,
+        icon,
+        icon_url,
+        condition,
+        forecasts,
+        daytime,
+        wind,
+        pressure,
+        precipitation,
+        astro,
+        alerts,
+        expires_at,
+        sources,
+    }
+}
+
+fn average(temps: impl Iterator<Item=Option<Temperature>>) -> Option<Temperature> {
+    let values: Vec<f64> = temps.flatten().map(|t| t.as_unit(Celsius).val()).collect();
+    if values.is_empty() {
+        return None;
+    }
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    Some(Temperature::new(avg, Celsius))
+}
+
+// при равенстве голосов побеждает состояние, встретившееся первым
+fn majority(conditions: impl Iterator<Item=Condition>) -> Option<Condition> {
+    let mut votes: Vec<(Condition, usize)> = Vec::new();
+    for condition in conditions {
+        match votes.iter_mut().find(|(c, _)| *c == condition) {
+            Some((_, count)) => *count += 1,
+            None => votes.push((condition, 1)),
+        }
+    }
+    let max = votes.iter().map(|(_, count)| *count).max()?;
+    votes.into_iter().find(|(_, count)| *count == max).map(|(c, _)| c)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::weather::weather::Forecast;
+
+    use super::*;
+
+    fn source(provider: &str, temp: i16, humidity: Option<u64>, condition: Option<Condition>) -> WeatherInfo {
+        WeatherInfo {
+            provider: Some(provider.to_string()),
+            temp: Some(Temperature::new(temp, Celsius)),
+            humidity,
+            icon: Some(provider.to_string()),
+            condition,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merge_sources() {
+        let w = merge(vec![
+            source("yandex", 1, Some(60), Some(Condition::Rain)),
+            source("openmeteo", 2, Some(80), Some(Condition::Cloudy)),
+            source("metno", 4, None, Some(Condition::Cloudy)),
+        ]);
+
+        assert_eq!(w.provider.as_deref(), Some("yandex+openmeteo+metno"));
+        assert_eq!(w.temp, Some(Temperature::new(7.0 / 3.0, Celsius)));
+        assert_eq!(w.feels_like, None);
+        assert_eq!(w.humidity, Some(80));
+        assert_eq!(w.condition, Some(Condition::Cloudy));
+        assert_eq!(w.icon.as_deref(), Some("openmeteo"));
+        assert_eq!(w.sources.len(), 3);
+
+        let forecast = Forecast { parts: vec![], days: vec![] };
+        let w = merge(vec![
+            source("yandex", 1, None, None),
+            WeatherInfo { forecasts: Some(forecast), ..source("metno", 2, None, None) },
+        ]);
+        assert!(w.forecasts.is_some());
+        assert!(w.sources[1].forecasts.is_some());
+    }
+
+    #[test]
+    fn majority_tie() {
+        let conditions = vec![Condition::Snow, Condition::Clear, Condition::Clear, Condition::Snow];
+        assert_eq!(majority(conditions.into_iter()), Some(Condition::Snow));
+        assert_eq!(majority(vec![].into_iter()), None);
+    }
+}
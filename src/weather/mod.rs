@@ -1,2 +1,8 @@
 pub mod weather;
-pub mod provider;
\ No newline at end of file
+pub mod provider;
+pub mod registry;
+pub mod fallback;
+pub mod merged;
+pub mod hourly;
+pub mod astro;
+pub mod icons;
@@ -1,12 +1,43 @@
+use serde::Deserialize;
+
 use crate::Error;
 use crate::weather::weather::WeatherInfo;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 pub enum WeatherQueryType {
-    All,
-    //Current,
-    //Forecast,
+    // текущая погода
+    Current,
+    // почасовой прогноз
+    Hourly,
+    // прогноз по частям суток и дням
+    Daily,
+    // предупреждения о погоде
+    Alerts,
+}
+
+impl WeatherQueryType {
+    pub const ALL: [WeatherQueryType; 4] = [
+        WeatherQueryType::Current,
+        WeatherQueryType::Hourly,
+        WeatherQueryType::Daily,
+        WeatherQueryType::Alerts,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeatherQueryType::Current => "current",
+            WeatherQueryType::Hourly => "hourly",
+            WeatherQueryType::Daily => "daily",
+            WeatherQueryType::Alerts => "alerts",
+        }
+    }
 }
 
 pub trait WeatherGetter {
     fn get(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error>;
+
+    // запрос в обход кэша, например по SIGUSR1
+    fn refresh(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        self.get(query)
+    }
 }
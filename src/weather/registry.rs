@@ -0,0 +1,128 @@
+use crate::config::config::{Config, Provider, Strategy};
+use crate::Error;
+use crate::metno::metno::MetNo;
+use crate::openmeteo::openmeteo::OpenMeteo;
+use crate::openweathermap::openweathermap::OpenWeatherMap;
+use crate::weather::fallback::FallbackProvider;
+use crate::weather::merged::MergedProvider;
+use crate::weather::provider::WeatherGetter;
+use crate::yandex::yandex::Yandex;
+
+// Конструктор возвращает None, если в конфиге нет секции провайдера
+type Constructor = fn(&Config) -> Option<Box<dyn WeatherGetter>>;
+
+struct Registration {
+    provider: Provider,
+    constructor: Constructor,
+}
+
+const PROVIDERS: &[Registration] = &[
+    Registration { provider: Provider::Yandex, constructor: yandex },
+    Registration { provider: Provider::OpenWeatherMap, constructor: openweathermap },
+    Registration { provider: Provider::OpenMeteo, constructor: openmeteo },
+    Registration { provider: Provider::MetNo, constructor: metno },
+];
+
+fn yandex(c: &Config) -> Option<Box<dyn WeatherGetter>> {
+    Some(Box::new(Yandex::new(c.yandex.clone()?)))
+}
+
+fn openweathermap(c: &Config) -> Option<Box<dyn WeatherGetter>> {
+    Some(Box::new(OpenWeatherMap::new(c.openweathermap.clone()?)))
+}
+
+fn openmeteo(c: &Config) -> Option<Box<dyn WeatherGetter>> {
+    Some(Box::new(OpenMeteo::new(c.openmeteo.clone()?)))
+}
+
+fn metno(c: &Config) -> Option<Box<dyn WeatherGetter>> {
+    Some(Box::new(MetNo::new(c.metno.clone()?)))
+}
+
+fn registration(provider: &Provider) -> Result<&'static Registration, Error> {
+    PROVIDERS.iter()
+        .find(|r| r.provider == *provider)
+        .ok_or_else(|| Error::InvalidConfigCheck(format!("unsupported provider {:?}", provider)))
+}
+
+fn missing_section(r: &Registration) -> Error {
+    Error::InvalidConfigCheck(format!("expected [{}] section for provider {:?}", r.provider.name(), r.provider))
+}
+
+// Проверяет, что провайдер зарегистрирован и его секция есть в конфиге
+pub fn check(provider: &Provider, config: &Config) -> Result<(), Error> {
+    let r = registration(provider)?;
+    (r.constructor)(config).map(|_| ()).ok_or_else(|| missing_section(r))
+}
+
+pub fn create(provider: &Provider, config: &Config) -> Result<Box<dyn WeatherGetter>, Error> {
+    let r = registration(provider)?;
+    (r.constructor)(config).ok_or_else(|| missing_section(r))
+}
+
+// Провайдер по настройкам: единственный, либо несколько providers по strategy
+pub fn build(config: &Config) -> Result<Box<dyn WeatherGetter>, Error> {
+    let mut providers = config.providers().iter()
+        .map(|provider| create(provider, config))
+        .collect::<Result<Vec<_>, Error>>()?;
+    if providers.len() == 1 {
+        return Ok(providers.remove(0));
+    }
+    Ok(match config.strategy {
+        Strategy::Fallback => Box::new(FallbackProvider::new(providers)),
+        Strategy::Merge => Box::new(MergedProvider::new(providers)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_provider_registered() {
+        use Provider::*;
+        for provider in [Yandex, OpenWeatherMap, OpenMeteo, MetNo] {
+            assert!(registration(&provider).is_ok(), "{:?}", provider);
+        }
+    }
+
+    #[test]
+    fn section_required() {
+        let config: Config = toml::from_str(r#"
+            provider = "OpenMeteo"
+
+            [yandex]
+            api_key = "key"
+            lat = "57.1522"
+            lon = "65.5272"
+            user_agent = "forecast"
+        "#).unwrap();
+
+        assert!(check(&Provider::Yandex, &config).is_ok());
+        assert!(create(&Provider::Yandex, &config).is_ok());
+
+        let err = check(&Provider::OpenMeteo, &config).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid config file: expected [openmeteo] section for provider OpenMeteo");
+        assert!(create(&Provider::OpenMeteo, &config).is_err());
+        assert!(build(&config).is_err());
+    }
+
+    #[test]
+    fn build_chain() {
+        let config: Config = toml::from_str(r#"
+            providers = ["MetNo", "OpenMeteo"]
+
+            [openmeteo]
+            lat = "57.1522"
+            lon = "65.5272"
+
+            [metno]
+            lat = "57.1522"
+            lon = "65.5272"
+            user_agent = "forecast"
+        "#).unwrap();
+
+        assert_eq!(config.providers(), vec![Provider::MetNo, Provider::OpenMeteo]);
+        assert!(build(&config).is_ok());
+    }
+}
@@ -1,47 +1,464 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
 
 use crate::temperature::Temperature;
+use crate::weather::provider::WeatherQueryType;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WeatherInfo {
     #[serde(default)]
     pub is_cached: bool,
 
+    // провайдер, вернувший данные
+    #[serde(default)]
+    pub provider: Option<String>,
+
     #[serde(default = "default_created_at")]
     pub created_at: SystemTime,
 
-    pub temp: Temperature,
+    pub temp: Option<Temperature>,
     pub feels_like: Option<Temperature>,
     pub humidity: Option<u64>,
     pub icon: Option<String>,
+    #[serde(default)]
+    pub icon_url: Option<String>,
     pub condition: Option<Condition>,
     pub forecasts: Option<Forecast>,
     pub daytime: Option<Daytime>,
+
+    #[serde(default)]
+    pub wind: Option<Wind>,
+    #[serde(default)]
+    pub pressure: Option<Pressure>,
+    #[serde(default)]
+    pub precipitation: Option<Precipitation>,
+
+    // восход, заход и фаза луны
+    #[serde(default)]
+    pub astro: Option<Astro>,
+
+    #[serde(default)]
+    pub alerts: Option<Vec<Alert>>,
+
+    // время устаревания данных по мнению провайдера
+    #[serde(default)]
+    pub expires_at: Option<SystemTime>,
+
+    // ответы отдельных провайдеров, из которых собраны данные
+    #[serde(default)]
+    pub sources: Vec<WeatherInfo>,
 }
 
 fn default_created_at() -> SystemTime {
     SystemTime::now()
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Default for WeatherInfo {
+    fn default() -> Self {
+        WeatherInfo {
+            is_cached: false,
+            provider: None,
+            created_at: SystemTime::now(),
+            temp: None,
+            feels_like: None,
+            humidity: None,
+            icon: None,
+            icon_url: None,
+            condition: None,
+            forecasts: None,
+            daytime: None,
+            wind: None,
+            pressure: None,
+            precipitation: None,
+            astro: None,
+            alerts: None,
+            expires_at: None,
+            sources: vec![],
+        }
+    }
+}
+
+impl WeatherInfo {
+    // данные только указанного вида
+    pub fn select(&self, kind: WeatherQueryType) -> WeatherInfo {
+        let mut w = WeatherInfo {
+            is_cached: self.is_cached,
+            provider: self.provider.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            sources: self.sources.iter().map(|source| source.select(kind)).collect(),
+            ..Default::default()
+        };
+        match kind {
+            WeatherQueryType::Current => {
+                w.temp = self.temp;
+                w.feels_like = self.feels_like;
+                w.humidity = self.humidity;
+                w.icon = self.icon.clone();
+                w.icon_url = self.icon_url.clone();
+                w.condition = self.condition;
+                w.daytime = self.daytime;
+                w.wind = self.wind;
+                w.pressure = self.pressure;
+                w.precipitation = self.precipitation;
+                w.astro = self.astro;
+            }
+            WeatherQueryType::Daily => {
+                w.forecasts = self.forecasts.as_ref().map(|f| Forecast {
+                    parts: f.parts.clone(),
+                    days: f.days.iter()
+                        .map(|day| ForecastDay { hours: vec![], ..day.clone() })
+                        .collect(),
+                });
+            }
+            WeatherQueryType::Hourly => {
+                w.forecasts = self.forecasts.as_ref().map(|f| Forecast {
+                    parts: vec![],
+                    days: f.days.iter()
+                        .filter(|day| !day.hours.is_empty())
+                        .map(|day| ForecastDay { hours: day.hours.clone(), ..ForecastDay::new(&day.date) })
+                        .collect(),
+                });
+            }
+            WeatherQueryType::Alerts => w.alerts = self.alerts.clone(),
+        }
+        w
+    }
+
+    // данные только запрошенных видов
+    pub fn filter(&self, query: &[WeatherQueryType]) -> WeatherInfo {
+        let mut w = WeatherInfo {
+            is_cached: self.is_cached,
+            provider: self.provider.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            ..Default::default()
+        };
+        for kind in query {
+            w.merge(self.select(*kind));
+        }
+        w
+    }
+
+    // дополняет данными другого вида
+    pub fn merge(&mut self, other: WeatherInfo) {
+        if self.provider.is_none() {
+            self.provider = other.provider;
+        }
+        self.created_at = self.created_at.min(other.created_at);
+        self.expires_at = match (self.expires_at, other.expires_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if other.temp.is_some() {
+            self.temp = other.temp;
+            self.feels_like = other.feels_like;
+            self.humidity = other.humidity;
+            self.icon = other.icon;
+            self.icon_url = other.icon_url;
+            self.condition = other.condition;
+            self.daytime = other.daytime;
+            self.wind = other.wind;
+            self.pressure = other.pressure;
+            self.precipitation = other.precipitation;
+            self.astro = other.astro;
+        }
+        if other.alerts.is_some() {
+            self.alerts = other.alerts;
+        }
+        self.forecasts = match (self.forecasts.take(), other.forecasts) {
+            (Some(mut forecast), Some(other)) => {
+                forecast.merge(other);
+                Some(forecast)
+            }
+            (a, b) => a.or(b),
+        };
+        for source in other.sources {
+            match self.sources.iter_mut().find(|s| s.provider == source.provider) {
+                Some(s) => s.merge(source),
+                None => self.sources.push(source),
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Alert {
+    pub event: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub severity: Option<String>,
+    pub start: Option<SystemTime>,
+    pub end: Option<SystemTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Forecast {
     pub parts: Vec<ForecastPart>,
+
+    // прогноз по дням
+    #[serde(default)]
+    pub days: Vec<ForecastDay>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForecastDay {
+    // дата в формате YYYY-MM-DD
+    pub date: String,
+    pub temp_min: Option<Temperature>,
+    pub temp_max: Option<Temperature>,
+    pub humidity: Option<u64>,
+    pub icon: Option<String>,
+    pub icon_url: Option<String>,
+    pub condition: Option<Condition>,
+    // части суток: night, morning, day, evening
+    pub parts: Vec<ForecastPart>,
+    // почасовой прогноз, name - час суток
+    pub hours: Vec<ForecastPart>,
+}
+
+impl ForecastDay {
+    pub fn new(date: &str) -> Self {
+        ForecastDay {
+            date: date.to_string(),
+            temp_min: None,
+            temp_max: None,
+            humidity: None,
+            icon: None,
+            icon_url: None,
+            condition: None,
+            parts: vec![],
+            hours: vec![],
+        }
+    }
+
+    fn merge(&mut self, other: ForecastDay) {
+        self.temp_min = self.temp_min.or(other.temp_min);
+        self.temp_max = self.temp_max.or(other.temp_max);
+        self.humidity = self.humidity.or(other.humidity);
+        self.icon = self.icon.take().or(other.icon);
+        self.icon_url = self.icon_url.take().or(other.icon_url);
+        self.condition = self.condition.or(other.condition);
+        if self.parts.is_empty() {
+            self.parts = other.parts;
+        }
+        if self.hours.is_empty() {
+            self.hours = other.hours;
+        }
+    }
+}
+
+impl Forecast {
+    fn merge(&mut self, other: Forecast) {
+        if self.parts.is_empty() {
+            self.parts = other.parts;
+        }
+        for day in other.days {
+            match self.days.iter_mut().find(|d| d.date == day.date) {
+                Some(d) => d.merge(day),
+                None => self.days.push(day),
+            }
+        }
+        self.days.sort_by(|a, b| a.date.cmp(&b.date));
+    }
+}
+
+pub const PART_NAMES: [&str; 4] = ["night", "morning", "day", "evening"];
+
+// (номер дня, индекс в PART_NAMES) для локального unix-времени
+pub fn part_of_day(local: i64) -> (i64, usize) {
+    let day = local.div_euclid(86400);
+    let hour = local.rem_euclid(86400) / 3600;
+    (day, hour as usize / 6)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ForecastPart {
     pub name: String,
     pub temp: Temperature,
     pub humidity: Option<u64>,
     pub icon: Option<String>,
+    #[serde(default)]
+    pub icon_url: Option<String>,
     pub condition: Option<Condition>,
     pub feels_like: Option<Temperature>,
     pub daytime: Option<Daytime>,
+    #[serde(default)]
+    pub wind: Option<Wind>,
+    #[serde(default)]
+    pub pressure: Option<Pressure>,
+    #[serde(default)]
+    pub precipitation: Option<Precipitation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Wind {
+    // скорость, м/с
+    pub speed: f64,
+    // порывы, м/с
+    pub gust: Option<f64>,
+    pub dir: Option<WindDirection>,
+}
+
+// откуда дует ветер
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WindDirection {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+    // штиль
+    Calm,
+}
+
+impl WindDirection {
+    // направление в градусах, 0 - северный ветер
+    pub fn from_degrees(degrees: f64) -> Self {
+        let sectors = [
+            WindDirection::N, WindDirection::NE, WindDirection::E, WindDirection::SE,
+            WindDirection::S, WindDirection::SW, WindDirection::W, WindDirection::NW,
+        ];
+        let sector = (degrees.rem_euclid(360.0) / 45.0).round() as usize % 8;
+        sectors[sector]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindDirection::N => "N",
+            WindDirection::NE => "NE",
+            WindDirection::E => "E",
+            WindDirection::SE => "SE",
+            WindDirection::S => "S",
+            WindDirection::SW => "SW",
+            WindDirection::W => "W",
+            WindDirection::NW => "NW",
+            WindDirection::Calm => "calm",
+        }
+    }
+
+    // стрелка показывает, куда дует ветер
+    pub fn arrow(&self) -> char {
+        match self {
+            WindDirection::N => '↓',
+            WindDirection::NE => '↙',
+            WindDirection::E => '←',
+            WindDirection::SE => '↖',
+            WindDirection::S => '↑',
+            WindDirection::SW => '↗',
+            WindDirection::W => '→',
+            WindDirection::NW => '↘',
+            WindDirection::Calm => '·',
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+// атмосферное давление, гПа
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Pressure(pub f64);
+
+impl Pressure {
+    const HPA_PER_MM_HG: f64 = 1.333_224;
+    const HPA_PER_IN_HG: f64 = 33.863_89;
+
+    pub fn from_mm_hg(mm: f64) -> Self {
+        Pressure(mm * Self::HPA_PER_MM_HG)
+    }
+
+    pub fn hpa(&self) -> f64 {
+        self.0
+    }
+
+    pub fn mm_hg(&self) -> f64 {
+        self.0 / Self::HPA_PER_MM_HG
+    }
+
+    pub fn in_hg(&self) -> f64 {
+        self.0 / Self::HPA_PER_IN_HG
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Astro {
+    pub sunrise: Option<SystemTime>,
+    pub sunset: Option<SystemTime>,
+    pub moon_phase: Option<MoonPhase>,
+}
+
+impl Astro {
+    // долгота дня
+    pub fn day_length(&self) -> Option<Duration> {
+        self.sunset?.duration_since(self.sunrise?).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum MoonPhase {
+    NewMoon,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    FullMoon,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    // 0 - новолуние, 4 - полнолуние
+    pub fn from_index(index: usize) -> Self {
+        match index % 8 {
+            0 => MoonPhase::NewMoon,
+            1 => MoonPhase::WaxingCrescent,
+            2 => MoonPhase::FirstQuarter,
+            3 => MoonPhase::WaxingGibbous,
+            4 => MoonPhase::FullMoon,
+            5 => MoonPhase::WaningGibbous,
+            6 => MoonPhase::LastQuarter,
+            _ => MoonPhase::WaningCrescent,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoonPhase::NewMoon => "new moon",
+            MoonPhase::WaxingCrescent => "waxing crescent",
+            MoonPhase::FirstQuarter => "first quarter",
+            MoonPhase::WaxingGibbous => "waxing gibbous",
+            MoonPhase::FullMoon => "full moon",
+            MoonPhase::WaningGibbous => "waning gibbous",
+            MoonPhase::LastQuarter => "last quarter",
+            MoonPhase::WaningCrescent => "waning crescent",
+        }
+    }
+
+    pub fn icon(&self) -> char {
+        match self {
+            MoonPhase::NewMoon => '🌑',
+            MoonPhase::WaxingCrescent => '🌒',
+            MoonPhase::FirstQuarter => '🌓',
+            MoonPhase::WaxingGibbous => '🌔',
+            MoonPhase::FullMoon => '🌕',
+            MoonPhase::WaningGibbous => '🌖',
+            MoonPhase::LastQuarter => '🌗',
+            MoonPhase::WaningCrescent => '🌘',
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Precipitation {
+    // количество осадков, мм
+    pub mm: Option<f64>,
+    // вероятность осадков, %
+    pub prob: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Condition {
     // ясно.
     Clear,
@@ -157,3 +574,25 @@ pub enum Daytime {
     Day,
     Night,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind_direction() {
+        assert_eq!(WindDirection::from_degrees(0.0), WindDirection::N);
+        assert_eq!(WindDirection::from_degrees(350.0), WindDirection::N);
+        assert_eq!(WindDirection::from_degrees(225.0), WindDirection::SW);
+        assert_eq!(WindDirection::from_degrees(-90.0), WindDirection::W);
+        assert_eq!(WindDirection::SW.arrow(), '↗');
+    }
+
+    #[test]
+    fn pressure_units() {
+        let p = Pressure::from_mm_hg(745.0);
+        assert_eq!(p.hpa().round(), 993.0);
+        assert_eq!(p.mm_hg().round(), 745.0);
+        assert_eq!((Pressure(1013.25).in_hg() * 100.0).round(), 2992.0);
+    }
+}
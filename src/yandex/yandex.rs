@@ -1,19 +1,26 @@
-use std::time::SystemTime;
+use std::time::{Duration, UNIX_EPOCH};
 
 use reqwest::blocking::Client;
 use reqwest::header::{HeaderMap, USER_AGENT};
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::config::yandex::ConfigYandex;
+use crate::config::config::Provider;
+use crate::config::yandex::{ConfigYandex, Endpoint};
 use crate::Error;
 use crate::Error::InvalidRequest;
 use crate::temperature::Temperature;
 use crate::temperature::Unit::Celsius;
 use crate::weather::provider::{WeatherGetter, WeatherQueryType};
-use crate::weather::weather::{Condition, Daytime, Forecast, ForecastPart, WeatherInfo};
+use crate::weather::astro;
+use crate::weather::weather::{Astro, Condition, Daytime, Forecast, ForecastDay, ForecastPart, part_of_day, PART_NAMES, MoonPhase, Precipitation, Pressure, WeatherInfo, Wind, WindDirection};
 
 const API_URL: &str = "https://api.weather.yandex.ru/v2/informers?";
+const API_URL_FORECAST: &str = "https://api.weather.yandex.ru/v2/forecast?";
+const ICON_URL: &str = "https://yastatic.net/weather/i/icons/funky/dark/";
+
+// столько частей суток отдаёт informers
+const FORECAST_PARTS: usize = 2;
 
 pub struct Yandex {
     config: ConfigYandex,
@@ -26,20 +33,46 @@ impl Yandex {
 }
 
 impl WeatherGetter for Yandex {
-    fn get(&self, _: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
-        let mut query_params = QueryParamsInformers {
+    fn get(&self, query: Vec<WeatherQueryType>) -> Result<WeatherInfo, Error> {
+        // предупреждений yandex не отдаёт
+        if query.iter().all(|kind| *kind == WeatherQueryType::Alerts) {
+            return Ok(WeatherInfo {
+                provider: Some(Provider::Yandex.name().to_string()),
+                ..Default::default()
+            });
+        }
+        let mut query_params = QueryParams {
             lon: self.config.lon.as_str(),
             lat: self.config.lat.as_str(),
             lang: None,
+            limit: None,
+            hours: None,
+            extra: None,
         };
         if let Some(ref lang) = self.config.lang {
             query_params.lang = Some(lang.as_str())
         }
+        let api_url = match self.config.endpoint {
+            Endpoint::Informers => API_URL,
+            Endpoint::Forecast => {
+                // лишние дни и часы не запрашиваются
+                query_params.limit = match query.contains(&WeatherQueryType::Daily) {
+                    true => self.config.limit,
+                    false => Some(1),
+                };
+                query_params.hours = match query.contains(&WeatherQueryType::Hourly) {
+                    true => self.config.hours,
+                    false => Some(false),
+                };
+                query_params.extra = self.config.extra;
+                API_URL_FORECAST
+            }
+        };
 
         let params = serde_qs::to_string(&query_params).
             expect("failed to format query params");
 
-        let url = API_URL.to_owned() + &params;
+        let url = api_url.to_owned() + &params;
 
         let client = Client::new();
 
@@ -60,58 +93,227 @@ impl WeatherGetter for Yandex {
         }
         let res: Value = response.json()?;
 
-        parse(res).ok_or(Error::InvalidResponse)
+        let mut weather = parse(res).ok_or(Error::InvalidResponse)?;
+        astro::complete(&mut weather, &self.config.lat, &self.config.lon);
+        Ok(weather.filter(&query))
     }
 }
 
 #[derive(Debug, Serialize)]
-struct QueryParamsInformers<'a> {
+struct QueryParams<'a> {
     lat: &'a str,
     lon: &'a str,
     lang: Option<&'a str>,
+    limit: Option<u8>,
+    hours: Option<bool>,
+    extra: Option<bool>,
 }
 
 fn parse(response: Value) -> Option<WeatherInfo> {
-    let temperature = response["fact"]["temp"].as_i64()?;
-    let temperature_like = response["fact"]["feels_like"].as_i64()?;
+    let temperature = response["fact"]["temp"].as_f64()?;
+    let temperature_like = response["fact"]["feels_like"].as_f64()?;
+    let icon = response["fact"]["icon"].as_str()?;
 
 
     Some(WeatherInfo {
-        is_cached: false,
-        created_at: SystemTime::now(),
-        temp: Temperature::new(temperature as i16, Celsius),
-        feels_like: Some(Temperature::new(temperature_like as i16, Celsius)),
+        provider: Some(Provider::Yandex.name().to_string()),
+        temp: Some(Temperature::new(temperature, Celsius)),
+        feels_like: Some(Temperature::new(temperature_like, Celsius)),
         humidity: response["fact"]["humidity"].as_u64(),
-        icon: Some(response["fact"]["icon"].as_str()?.to_string()),
+        icon: Some(icon.to_string()),
+        icon_url: Some(icon_url(icon)),
         condition: parse_condition(response["fact"]["condition"].as_str()),
-        forecasts: parse_forecast(&response["forecast"]),
+        forecasts: parse_forecast(&response),
         daytime: parse_daytime(response["fact"]["daytime"].as_str()),
+        wind: parse_wind(&response["fact"]),
+        pressure: parse_pressure(&response["fact"]),
+        precipitation: parse_precipitation(&response["fact"]),
+        astro: parse_astro(&response),
+        ..Default::default()
     })
 }
 
+// informers отдаёт две ближайшие части суток в forecast.parts,
+// forecast - прогноз по дням в forecasts
 fn parse_forecast(response: &Value) -> Option<Forecast> {
+    if let Some(days) = response["forecasts"].as_array() {
+        return parse_days(response, days);
+    }
+    let mut forecast = Forecast {
+        parts: Vec::new(),
+        days: Vec::new(),
+    };
+    for part in response["forecast"]["parts"].as_array()? {
+        let forecast_part = parse_part(part["part_name"].as_str()?, part)?;
+        forecast.parts.push(forecast_part);
+    }
+    Some(forecast)
+}
+
+fn parse_days(response: &Value, days: &[Value]) -> Option<Forecast> {
+    let offset = response["info"]["tzinfo"]["offset"].as_i64().unwrap_or(0);
+    let now = part_of_day(response["now"].as_i64()? + offset);
+
     let mut forecast = Forecast {
         parts: Vec::new(),
+        days: Vec::new(),
     };
-    for part in response["parts"].as_array()? {
-        let temperature = part["temp_avg"].as_i64()?;
-        let temperature_like = part["feels_like"].as_i64()?;
-
-        let forecast_part = ForecastPart {
-            name: part["part_name"].as_str()?.to_string(),
-            temp: Temperature::new(temperature as i16, Celsius),
-            feels_like: Some(Temperature::new(temperature_like as i16, Celsius)),
-            humidity: part["humidity"].as_u64(),
-            condition: parse_condition(part["condition"].as_str()),
-            icon: Some(part["icon"].as_str()?.to_string()),
-            daytime: parse_daytime(part["daytime"].as_str()),
+    for day in days {
+        let day_number = part_of_day(day["date_ts"].as_i64()? + offset).0;
+
+        let mut parts = Vec::new();
+        for (i, name) in PART_NAMES.iter().enumerate() {
+            if let Some(part) = parse_part(name, &day["parts"][name]) {
+                // как в informers: две ближайшие части суток после текущей
+                if (day_number, i) > now && forecast.parts.len() < FORECAST_PARTS {
+                    forecast.parts.push(part.clone());
+                }
+                parts.push(part);
+            }
+        }
+
+        let sunrise = parse_hour(day["sunrise"].as_str());
+        let sunset = parse_hour(day["sunset"].as_str());
+        let mut hours = Vec::new();
+        // час без нужных полей пропускается, а не лишает прогноза целиком
+        let valid_hours = day["hours"].as_array().into_iter().flatten()
+            .filter_map(|hour| parse_part(hour["hour"].as_str()?, hour));
+        for mut forecast_hour in valid_hours {
+            if let (Some(sunrise), Some(sunset), Ok(h)) = (sunrise, sunset, forecast_hour.name.parse::<u32>()) {
+                let is_day = sunrise <= h * 60 && h * 60 < sunset;
+                forecast_hour.daytime = Some(if is_day { Daytime::Day } else { Daytime::Night });
+            }
+            hours.push(forecast_hour);
+        }
+
+        let summary = if day["parts"]["day_short"].is_object() {
+            &day["parts"]["day_short"]
+        } else {
+            &day["parts"]["day"]
         };
+        let icon = summary["icon"].as_str();
 
-        forecast.parts.push(forecast_part);
+        forecast.days.push(ForecastDay {
+            date: day["date"].as_str()?.to_string(),
+            temp_min: parts.iter().map(|p| p.temp).chain(day_temperatures(day, "temp_min")).min(),
+            temp_max: parts.iter().map(|p| p.temp).chain(day_temperatures(day, "temp_max")).max(),
+            humidity: summary["humidity"].as_u64(),
+            icon: icon.map(|icon| icon.to_string()),
+            icon_url: icon.map(icon_url),
+            condition: parse_condition(summary["condition"].as_str()),
+            parts,
+            hours,
+        });
     }
     Some(forecast)
 }
 
+// informers: forecast, forecast: первый день из forecasts
+fn parse_astro(response: &Value) -> Option<Astro> {
+    let day = match response["forecasts"].as_array() {
+        Some(days) => days.first()?,
+        None => &response["forecast"],
+    };
+    let midnight = day["date_ts"].as_i64()?;
+    let time = |field: &str| parse_hour(day[field].as_str())
+        .map(|minutes| UNIX_EPOCH + Duration::from_secs((midnight + minutes as i64 * 60) as u64));
+    Some(Astro {
+        sunrise: time("sunrise"),
+        sunset: time("sunset"),
+        moon_phase: day["moon_code"].as_u64().map(parse_moon_code),
+    })
+}
+
+// 0 - полнолуние, 4 - последняя четверть, 8 - новолуние, 12 - первая четверть
+fn parse_moon_code(code: u64) -> MoonPhase {
+    match code % 16 {
+        0 => MoonPhase::FullMoon,
+        1..=3 => MoonPhase::WaningGibbous,
+        4 => MoonPhase::LastQuarter,
+        5..=7 => MoonPhase::WaningCrescent,
+        8 => MoonPhase::NewMoon,
+        9..=11 => MoonPhase::WaxingCrescent,
+        12 => MoonPhase::FirstQuarter,
+        _ => MoonPhase::WaxingGibbous,
+    }
+}
+
+// значение поля по всем частям суток
+fn day_temperatures<'a>(day: &'a Value, field: &'a str) -> impl Iterator<Item=Temperature> + 'a {
+    PART_NAMES.iter()
+        .filter_map(move |name| day["parts"][name][field].as_f64())
+        .map(|t| Temperature::new(t, Celsius))
+}
+
+// "06:42" -> минуты от начала суток
+fn parse_hour(s: Option<&str>) -> Option<u32> {
+    let (hours, minutes) = s?.split_once(':')?;
+    Some(hours.parse::<u32>().ok()? * 60 + minutes.parse::<u32>().ok()?)
+}
+
+// часть суток (temp_avg) или час (temp)
+fn parse_part(name: &str, part: &Value) -> Option<ForecastPart> {
+    let temperature = part["temp_avg"].as_f64().or_else(|| part["temp"].as_f64())?;
+    let temperature_like = part["feels_like"].as_f64()?;
+    let icon = part["icon"].as_str()?;
+
+    Some(ForecastPart {
+        name: name.to_string(),
+        temp: Temperature::new(temperature, Celsius),
+        feels_like: Some(Temperature::new(temperature_like, Celsius)),
+        humidity: part["humidity"].as_u64(),
+        condition: parse_condition(part["condition"].as_str()),
+        icon: Some(icon.to_string()),
+        icon_url: Some(icon_url(icon)),
+        daytime: parse_daytime(part["daytime"].as_str()),
+        wind: parse_wind(part),
+        pressure: parse_pressure(part),
+        precipitation: parse_precipitation(part),
+    })
+}
+
+fn parse_wind(v: &Value) -> Option<Wind> {
+    Some(Wind {
+        speed: v["wind_speed"].as_f64()?,
+        gust: v["wind_gust"].as_f64(),
+        dir: parse_wind_dir(v["wind_dir"].as_str()),
+    })
+}
+
+fn parse_wind_dir(s: Option<&str>) -> Option<WindDirection> {
+    Some(match s? {
+        "n" => WindDirection::N,
+        "ne" => WindDirection::NE,
+        "e" => WindDirection::E,
+        "se" => WindDirection::SE,
+        "s" => WindDirection::S,
+        "sw" => WindDirection::SW,
+        "w" => WindDirection::W,
+        "nw" => WindDirection::NW,
+        "c" => WindDirection::Calm,
+        _ => return None,
+    })
+}
+
+// pressure_pa у yandex в гектопаскалях
+fn parse_pressure(v: &Value) -> Option<Pressure> {
+    v["pressure_pa"].as_f64().map(Pressure)
+        .or_else(|| v["pressure_mm"].as_f64().map(Pressure::from_mm_hg))
+}
+
+fn parse_precipitation(v: &Value) -> Option<Precipitation> {
+    let mm = v["prec_mm"].as_f64();
+    let prob = v["prec_prob"].as_u64();
+    if mm.is_none() && prob.is_none() {
+        return None;
+    }
+    Some(Precipitation { mm, prob })
+}
+
+fn icon_url(icon: &str) -> String {
+    format!("{}{}.svg", ICON_URL, icon)
+}
+
 fn parse_condition(s: Option<&str>) -> Option<Condition> {
     if let Some(condition) = s {
         return Some(match condition {
@@ -149,4 +351,72 @@ fn parse_daytime(s: Option<&str>) -> Option<Daytime> {
         },
         None => None,
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/yandex_forecast.json");
+
+    #[test]
+    fn parse_forecast_endpoint() {
+        let w = parse(serde_json::from_str(FIXTURE).unwrap()).unwrap();
+        assert_eq!(w.temp, Some(Temperature::new(4, Celsius)));
+        assert_eq!(w.daytime, Some(Daytime::Day));
+        assert_eq!(w.wind, Some(Wind { speed: 4.0, gust: Some(8.2), dir: Some(WindDirection::SW) }));
+        assert_eq!(w.pressure, Some(Pressure(993.0)));
+        let astro = w.astro.unwrap();
+        // 06:31 и 19:24 по UTC+5
+        assert_eq!(astro.sunrise, Some(UNIX_EPOCH + Duration::from_secs(1648431060)));
+        assert_eq!(astro.day_length(), Some(Duration::from_secs(12 * 3600 + 53 * 60)));
+        assert_eq!(astro.moon_phase, Some(MoonPhase::WaxingCrescent));
+
+        let forecast = w.forecasts.clone().unwrap();
+        assert_eq!(forecast.parts[0].precipitation, Some(Precipitation { mm: Some(0.0), prob: Some(0) }));
+        assert_eq!(forecast.parts.len(), 2);
+        assert_eq!(forecast.parts[0].name, "evening");
+        assert_eq!(forecast.parts[0].temp, Temperature::new(2, Celsius));
+        assert_eq!(forecast.parts[1].name, "night");
+        assert_eq!(forecast.parts[1].condition, Some(Condition::WetSnow));
+
+        assert_eq!(forecast.days.len(), 2);
+        let today = &forecast.days[0];
+        assert_eq!(today.date, "2022-03-28");
+        assert_eq!(today.temp_min, Some(Temperature::new(-5, Celsius)));
+        assert_eq!(today.temp_max, Some(Temperature::new(5, Celsius)));
+        assert_eq!(today.condition, Some(Condition::Cloudy));
+        assert_eq!(today.parts.len(), 4);
+
+        let hours: Vec<(&str, Option<Daytime>)> = today.hours.iter()
+            .map(|h| (h.name.as_str(), h.daytime))
+            .collect();
+        assert_eq!(hours, vec![("5", Some(Daytime::Night)), ("12", Some(Daytime::Day)), ("21", Some(Daytime::Night))]);
+        assert!(forecast.days[1].hours.is_empty());
+
+        let daily = w.filter(&[WeatherQueryType::Daily]);
+        assert_eq!(daily.temp, None);
+        let forecast = daily.forecasts.unwrap();
+        assert_eq!(forecast.parts.len(), 2);
+        assert!(forecast.days[0].hours.is_empty());
+    }
+
+    #[test]
+    fn bad_hour() {
+        let mut response: Value = serde_json::from_str(FIXTURE).unwrap();
+        response["forecasts"][0]["hours"][1]["feels_like"] = Value::Null;
+        response["forecasts"][0]["hours"][2]["hour"] = Value::Null;
+        let forecast = parse(response).unwrap().forecasts.unwrap();
+        let hours: Vec<&str> = forecast.days[0].hours.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(hours, vec!["5"]);
+        assert_eq!(forecast.days.len(), 2);
+        assert_eq!(forecast.parts.len(), 2);
+    }
+
+    #[test]
+    fn hours() {
+        assert_eq!(parse_hour(Some("06:31")), Some(391));
+        assert_eq!(parse_hour(Some("bad")), None);
+        assert_eq!(parse_hour(None), None);
+    }
+}